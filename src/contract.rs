@@ -1,13 +1,30 @@
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
-use crate::state::{Config, ConfigResponse, VestingInfo, CONFIG, VESTING_INFO};
+use crate::msg::{
+    ClaimHookExecuteMsg, ClaimedPosition, Cw721ReceiveMsg, ExecuteMsg, IbcClaim, InstantiateMsg,
+    MigrateMsg, QueryMsg, ReceiverExecuteMsg, VestingClaimHook,
+};
+use crate::state::{
+    split_amount, BasketCoin, ClaimHookResponse, ClaimedAmount, Clock, Config, ConfigResponse,
+    ContractInfoResponse, CreditsResponse, Delegation, DelegatorInfo, ForfeitMode, IbcPayout,
+    IbcPayoutsResponse, LegacyVestingInfo, NftInfoResponse, NumTokensResponse, OwnerOfResponse,
+    PauseInfo, Payout, RewardIndex, RewardsResponse, Role, Roles, RolesResponse, ScheduleResponse,
+    ScheduledUnlock, Stats, TokensResponse, Unbonding, UnlockStep, Vesting, VestingInfo,
+    VestingMode, VestingsResponse, VoteRecord, VotesResponse, CLAIM_HOOKS, CONFIG, CREDITS,
+    FROZEN_RECIPIENTS, IBC_PAYOUTS, IBC_PAYOUT_POSITIONS, IBC_PAYOUT_TOTALS, LEGACY_CONFIG,
+    LEGACY_VESTING_INFO, PAYOUT, PAYOUT_ID, REDISTRIBUTION, REWARDS, ROLES, STATS, VALIDATORS,
+    VALIDATOR_DELEGATIONS, VESTING_ID, VESTING_INFO, VESTING_RECIPIENT, VOTES, VOTE_TALLIES,
+};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    coins, to_binary, BankMsg, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response,
-    StdResult, SubMsg, Uint128,
+    coins, to_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, Decimal, Deps, DepsMut,
+    DistributionMsg, Empty, Env, IbcMsg, IbcTimeout, MessageInfo, Order, QuerierWrapper,
+    QueryRequest, Reply, Response, StakingMsg, StdError, StdResult, Storage, SubMsg, SubMsgResult,
+    Timestamp, Uint128, VoteOption, WasmMsg, WasmQuery,
 };
 use cw2::set_contract_version;
+use cw_storage_plus::Bound;
+use std::collections::BTreeMap;
 
 /// Contract name that is used for migration.
 const CONTRACT_NAME: &str = "crates.io:terra-emergency-vesting";
@@ -20,9 +37,32 @@ pub const SECONDS_PER_PERIOD: u64 = 60u64 * 60u64 * 24u64 * 30u64;
 // Number of periods in each Tollgate.
 pub const PERIODS_PER_TOLL: u64 = 6;
 
+// Amount of seconds in each day, used for calendar month periods.
+const SECONDS_PER_DAY: u64 = 60u64 * 60u64 * 24u64;
+
 // Denom of vested tokens
 pub const DENOM: &str = "uluna";
 
+// Default unbonding period of the staking module, in seconds.
+pub const UNBONDING_PERIOD: u64 = 60u64 * 60u64 * 24u64 * 21u64;
+
+// Default timeout of IBC payouts, in seconds.
+pub const IBC_TIMEOUT_SECONDS: u64 = 60u64 * 60u64;
+
+// Seconds after their timeout from which unresolved IBC payouts may be pruned as delivered.
+pub const IBC_PAYOUT_EXPIRY_SECONDS: u64 = 60u64 * 60u64 * 24u64 * 7u64;
+
+// Reply id of bank payouts, as IBC payouts are replied to with their payout ids starting at 1.
+const PAYOUT_REPLY_ID: u64 = 0;
+
+// Name and symbol of the CW721 collection of vesting positions
+const NFT_NAME: &str = "Emergency LUNA Allocation Vesting";
+const NFT_SYMBOL: &str = "VEST";
+
+// Default and maximum number of items returned by the paginated queries
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
 /// ## Description
 /// Creates a new contract with the specified parameters packed in the `msg` variable.
 /// Returns a [`Response`] with the specified attributes if the operation was successful,
@@ -38,7 +78,7 @@ pub const DENOM: &str = "uluna";
 /// - **msg**  is a message of type [`InstantiateMsg`] which contains the parameters used for creating the contract.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
@@ -49,287 +89,4119 @@ pub fn instantiate(
         None => info.sender,
     };
 
-    // Check sent vesting asset denom
-    if info.funds.len() != 1 || info.funds[0].denom != DENOM {
+    // Set `forfeit_address` as specified; otherwise, the master
+    let forfeit_address = match msg.forfeit_address {
+        Some(addr) => deps.api.addr_validate(&addr)?,
+        None => master_address.clone(),
+    };
+
+    // Periods are measured by block time unless specified
+    let clock = msg.clock.unwrap_or(Clock::Time);
+    if let Clock::Height {
+        blocks_per_period: 0,
+    } = clock
+    {
+        return Err(ContractError::InvalidClock {});
+    }
+    let vesting_start_time = clock_now(&clock, &env);
+
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            forfeit_address,
+            forfeit_mode: msg.forfeit_mode.unwrap_or(ForfeitMode::Transfer),
+            denom: DENOM.to_string(),
+            vesting_start_time,
+            clock,
+            pause: None,
+            acceptance_period: msg.acceptance_period,
+            claim_expiry_duration: msg.claim_expiry_duration,
+            unbonding_period: msg.unbonding_period.unwrap_or(UNBONDING_PERIOD),
+            transferable: msg.transferable.unwrap_or(false),
+        },
+    )?;
+
+    store_vestings(deps.branch(), &env, &info.funds, msg.vestings)?;
+
+    // `master_address` starts with all roles
+    ROLES.save(
+        deps.storage,
+        &Roles {
+            admins: vec![master_address.clone()],
+            reviewers: vec![master_address.clone()],
+            guardians: vec![master_address.clone()],
+            funders: vec![master_address.clone()],
+        },
+    )?;
+
+    STATS.save(deps.storage, &Stats::default())?;
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "instantiate")
+        .add_attribute("master_address", master_address)
+        .add_attribute("vesting_start_time", vesting_start_time.to_string()))
+}
+
+/// ## Description
+/// Validates the sent funds against the sum of `vestings` and stores each of them as a new vesting
+/// position of its recipient.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **funds** is a slice of [`Coin`] which are the funds sent along with the message.
+///
+/// - **vestings** is a vector of [`Vesting`] which are the vestings to be stored.
+fn store_vestings(
+    deps: DepsMut,
+    env: &Env,
+    funds: &[Coin],
+    vestings: Vec<Vesting>,
+) -> Result<(), ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    // In acceptance mode, recipients must accept their vestings before the deadline
+    let acceptance_deadline = config
+        .acceptance_period
+        .map(|acceptance_period| env.block.time.seconds() + acceptance_period);
+
+    // Sum the vesting amounts per denom, including the baskets
+    let mut sum_vesting_amounts: BTreeMap<String, Uint128> = BTreeMap::new();
+    for vesting in &vestings {
+        *sum_vesting_amounts.entry(DENOM.to_string()).or_default() += vesting.amount;
+        for basket_coin in vesting.basket.iter().flatten() {
+            *sum_vesting_amounts
+                .entry(basket_coin.denom.clone())
+                .or_default() += basket_coin.amount;
+        }
+    }
+
+    // Check sent vesting asset denoms
+    let sent_amounts = merge_coins(funds.to_vec());
+    if sent_amounts.len() != sum_vesting_amounts.len()
+        || sent_amounts
+            .iter()
+            .any(|coin| !sum_vesting_amounts.contains_key(&coin.denom))
+    {
         return Err(ContractError::MismatchedAssetType {});
     }
-    let sent_amount = info.funds[0].amount;
 
-    // Check sent vesting asset amount
-    let sum_vesting_amount: Uint128 = msg
-        .vestings
+    // Check sent vesting asset amounts
+    if sent_amounts
         .iter()
-        .fold(Uint128::zero(), |sum, recipient| sum + recipient.amount);
-    if sent_amount != sum_vesting_amount {
+        .any(|coin| sum_vesting_amounts[&coin.denom] != coin.amount)
+    {
         return Err(ContractError::MismatchedAssetAmount {});
     }
 
-    // Store each recipient's vesting info
-    for vesting in msg.vestings {
-        match VESTING_INFO.may_load(deps.storage, &deps.api.addr_validate(&vesting.recipient)?) {
-            Ok(None) => (),
-            Ok(Some(_)) => return Err(ContractError::DuplicatedRecipient {}),
-            Err(e) => return Err(ContractError::Std(e)),
-        }
-
-        // Get each recipient's total vesting periods based on the vesting amount
-        let total_periods = if vesting.amount > Uint128::new(50_000_000_000u128) {
-            6u64
-        } else {
-            3u64
-        };
-
+    // Store each vesting as a new position with the next vesting id, which is only credited
+    // with the forfeitures redistributed from then on
+    let redistribution_index = REDISTRIBUTION
+        .may_load(deps.storage)?
+        .unwrap_or_default()
+        .index;
+    let mut vesting_id = VESTING_ID.may_load(deps.storage)?.unwrap_or_default();
+    for vesting in vestings {
+        vesting_id += 1;
         if vesting.amount == Uint128::new(0u128) {
             return Err(ContractError::ZeroVestingAmount {
                 address: vesting.recipient,
             });
         }
 
+        // Vestings start at the time they are added unless specified
+        let start_time = vesting
+            .start_time
+            .unwrap_or_else(|| clock_now(&config.clock, env));
+        let mode = vesting.mode.unwrap_or(VestingMode::Step);
+
+        // Get each recipient's total vesting periods based on the vesting amount,
+        // or the last unlock step for custom schedules
+        let total_periods = match &mode {
+            VestingMode::Custom { steps } => {
+                validate_unlock_steps(steps, vesting.amount, start_time)?;
+                steps
+                    .iter()
+                    .map(|step| step_period(step, &config.clock, start_time))
+                    .max()
+                    .unwrap_or_default()
+                    .max(1u64)
+            }
+            VestingMode::Milestones { tranches } => {
+                let sum_tranche_amount = tranches
+                    .iter()
+                    .fold(Uint128::zero(), |sum, tranche| sum + tranche);
+                if tranches.is_empty() || sum_tranche_amount != vesting.amount {
+                    return Err(ContractError::InvalidMilestoneTranches {});
+                }
+                tranches.len() as u64
+            }
+            _ if vesting.amount > Uint128::new(50_000_000_000u128) => 6u64,
+            _ => 3u64,
+        };
+
+        // All vestings start with one approved tollgate, except milestones which all need approval
+        let approved_periods = match &vesting.tollgate_periods {
+            _ if matches!(mode, VestingMode::Milestones { .. }) => {
+                if vesting.tollgate_periods.is_some() {
+                    return Err(ContractError::InvalidTollgatePeriods {});
+                }
+                0u64
+            }
+            Some(tollgate_periods) => {
+                validate_tollgate_periods(tollgate_periods, total_periods)?;
+                tollgate_periods.first().copied().unwrap_or(total_periods)
+            }
+            None => PERIODS_PER_TOLL,
+        };
+
+        // Basket coins must be of distinct denoms other than the vesting denom
+        let basket = vesting.basket.unwrap_or_default();
+        let mut basket_denoms: Vec<&str> = basket
+            .iter()
+            .map(|basket_coin| basket_coin.denom.as_str())
+            .collect();
+        basket_denoms.sort_unstable();
+        basket_denoms.dedup();
+        if basket_denoms.len() != basket.len()
+            || basket
+                .iter()
+                .any(|basket_coin| basket_coin.denom == DENOM || basket_coin.amount.is_zero())
+        {
+            return Err(ContractError::InvalidBasket {});
+        }
+
         let vesting_info = VestingInfo {
             recipient: deps.api.addr_validate(&vesting.recipient)?,
+            vesting_id,
             active: true,
-            approved_periods: PERIODS_PER_TOLL, // all vestings start with one approved tollgate
+            approved_periods,
             total_periods,
             last_claimed_period: 0u64,
             total_amount: vesting.amount,
             claimed_amount: Uint128::zero(),
             vested_amount: vesting.amount,
             amount_per_period: vesting.amount / Uint128::from(total_periods),
+            acceptance_deadline,
+            start_time,
+            cliff_duration: vesting.cliff_duration.unwrap_or_default(),
+            mode,
+            tollgate_periods: vesting.tollgate_periods,
+            basket: basket
+                .into_iter()
+                .map(|basket_coin| BasketCoin {
+                    denom: basket_coin.denom,
+                    total_amount: basket_coin.amount,
+                    amount_per_period: basket_coin.amount / Uint128::from(total_periods),
+                    claimed_amount: Uint128::zero(),
+                    vested_amount: basket_coin.amount,
+                })
+                .collect(),
+            expired: false,
+            delegations: vec![],
+            unbondings: vec![],
+            forfeit_pending: Uint128::zero(),
+            redistribution_index,
         };
 
-        VESTING_INFO.save(
-            deps.storage,
-            &deps.api.addr_validate(&vesting.recipient)?,
-            &vesting_info,
-        )?;
+        save_vesting(deps.storage, &vesting_info)?;
+        VESTING_RECIPIENT.save(deps.storage, vesting_id, &vesting_info.recipient)?;
     }
+    VESTING_ID.save(deps.storage, &vesting_id)?;
 
-    CONFIG.save(
-        deps.storage,
-        &Config {
-            master_address: master_address.clone(),
-            denom: DENOM.to_string(),
-            vesting_start_time: env.block.time.seconds(),
-        },
-    )?;
-
-    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-
-    Ok(Response::new()
-        .add_attribute("method", "instantiate")
-        .add_attribute("master_address", master_address)
-        .add_attribute("vesting_start_time", env.block.time.seconds().to_string()))
+    Ok(())
 }
 
 /// ## Description
-/// Exposes all the execute functions available in the contract.
+/// Loads the vesting position `vesting_id` of `recipient`, or its only position if not specified.
+/// Returns a [`ContractError::AmbiguousVesting`] if the position is not specified and the recipient
+/// has several.
 ///
 /// ## Params
-/// - **deps** is an object of type [`DepsMut`].
-///
-/// - **env** is an object of type [`Env`].
+/// - **storage** is an object of type [`Storage`].
 ///
-/// - **info** is an object of type [`MessageInfo`].
+/// - **recipient** is an object of type [`Addr`] which is the recipient of the position.
 ///
-/// - **msg** is an object of type [`ExecuteMsg`].
+/// - **vesting_id** is an [`Option`] of type [`u64`] which is the id of the position.
+fn load_vesting(
+    storage: &dyn Storage,
+    recipient: &Addr,
+    vesting_id: Option<u64>,
+) -> Result<VestingInfo, ContractError> {
+    let mut vesting_info = match vesting_id {
+        Some(vesting_id) => VESTING_INFO.load(storage, (recipient, vesting_id))?,
+        None => {
+            let mut positions = VESTING_INFO
+                .prefix(recipient)
+                .range(storage, None, None, Order::Ascending)
+                .take(2)
+                .collect::<StdResult<Vec<(u64, VestingInfo)>>>()?;
+            match positions.len() {
+                0 => return Err(StdError::not_found(std::any::type_name::<VestingInfo>()).into()),
+                1 => positions.remove(0).1,
+                _ => return Err(ContractError::AmbiguousVesting {}),
+            }
+        }
+    };
+    settle_redistribution(storage, &mut vesting_info)?;
+    Ok(vesting_info)
+}
+
+/// ## Description
+/// Credits the share of the redistributed forfeitures not yet credited to a vesting as a top up.
+/// Vestings are settled when loaded, before their eligibility or total amount can change.
 ///
-/// ## Commands
-/// - **ExecuteMsg::ApproveTollgate {
-///             recipient,
-///             approve,
-///         }** Updates the tollgate / approve status of a recipient's vesting status.
+/// ## Params
+/// - **storage** is an object of type [`Storage`].
 ///
-/// - **ExecuteMsg::Claim {}** Claims any eligible vesting amount.
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn execute(
-    deps: DepsMut,
-    env: Env,
-    info: MessageInfo,
-    msg: ExecuteMsg,
-) -> Result<Response, ContractError> {
-    match msg {
-        ExecuteMsg::ApproveTollgate { recipient, approve } => {
-            try_approve_tollgate(deps, env, info, recipient, approve)
-        }
-        ExecuteMsg::Claim {} => try_claim(deps, env, info),
+/// - **vesting_info** is an object of type [`VestingInfo`] which is the vesting to be settled.
+fn settle_redistribution(storage: &dyn Storage, vesting_info: &mut VestingInfo) -> StdResult<()> {
+    let index = REDISTRIBUTION.may_load(storage)?.unwrap_or_default().index;
+    let share = vesting_info.redistribution_share(index);
+    if !share.is_zero() {
+        vesting_info.top_up(share);
     }
+    vesting_info.redistribution_index = index;
+    Ok(())
 }
 
 /// ## Description
-/// Updates the tollgate / approve status of a recipient's vesting status.
+/// Updates the total weight of the vestings eligible for redistribution, as the weight of a
+/// vesting changes from `old_weight` to `new_weight`.
 ///
 /// ## Params
-/// - **deps** is an object of type [`DepsMut`].
+/// - **storage** is an object of type [`Storage`].
 ///
-/// - **env** is an object of type [`Env`].
+/// - **old_weight** is an object of type [`Uint128`] which is the previous weight of the vesting.
 ///
-/// - **info** is an object of type [`MessageInfo`].
-pub fn try_claim(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
-    let config: Config = CONFIG.load(deps.storage)?;
-    let mut vesting_info = VESTING_INFO.load(deps.storage, &info.sender)?;
-
-    // Compute the number of periods has passed since genesis
-    let periods_since_genesis =
-        (env.block.time.seconds() - config.vesting_start_time) / SECONDS_PER_PERIOD;
-    // Calculate the total eligible periods -- including claimed and unclaimed periods
-    let eligible_periods = std::cmp::min(periods_since_genesis, vesting_info.approved_periods);
-    // Get only unclaimed periods
-    let claimable_periods = eligible_periods - vesting_info.last_claimed_period;
-    // Compute claimable amounts according to the unclaimed periods
-    let claimable_amount = vesting_info.amount_per_period * Uint128::from(claimable_periods);
-    if claimable_amount == Uint128::zero() {
-        return Err(ContractError::NoClaimable {});
+/// - **new_weight** is an object of type [`Uint128`] which is the new weight of the vesting.
+fn update_redistribution_weight(
+    storage: &mut dyn Storage,
+    old_weight: Uint128,
+    new_weight: Uint128,
+) -> StdResult<()> {
+    if old_weight == new_weight {
+        return Ok(());
     }
+    let mut redistribution = REDISTRIBUTION.may_load(storage)?.unwrap_or_default();
+    redistribution.total_weight = redistribution.total_weight - old_weight + new_weight;
+    REDISTRIBUTION.save(storage, &redistribution)
+}
 
-    // Update recipient's vesting info
-    vesting_info.claimed_amount += claimable_amount;
-    vesting_info.vested_amount -= claimable_amount;
-    vesting_info.last_claimed_period = eligible_periods;
+/// ## Description
+/// Stores a vesting position under its recipient and vesting id.
+///
+/// ## Params
+/// - **storage** is an object of type [`Storage`].
+///
+/// - **vesting_info** is an object of type [`VestingInfo`] which is the position to be stored.
+fn save_vesting(storage: &mut dyn Storage, vesting_info: &VestingInfo) -> StdResult<()> {
+    let key = (&vesting_info.recipient, vesting_info.vesting_id);
+    let (old_weight, old_delegations) = match VESTING_INFO.may_load(storage, key)? {
+        Some(old_vesting_info) => (
+            old_vesting_info.redistribution_weight(),
+            old_vesting_info.delegations,
+        ),
+        None => (Uint128::zero(), vec![]),
+    };
+    update_redistribution_weight(storage, old_weight, vesting_info.redistribution_weight())?;
+    update_validator_delegations(
+        storage,
+        &vesting_info.recipient,
+        vesting_info.vesting_id,
+        &old_delegations,
+        &vesting_info.delegations,
+    )?;
+    VESTING_INFO.save(storage, key, vesting_info)
+}
 
-    VESTING_INFO.save(deps.storage, &info.sender, &vesting_info)?;
-    Ok(Response::new()
-        .add_submessage(SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-            to_address: vesting_info.recipient.to_string(),
-            amount: coins(claimable_amount.into(), config.denom),
-        })))
-        .add_attribute("method", "try_claim")
-        .add_attribute("recipient", info.sender)
-        .add_attribute("claimed_amount", claimable_amount)
-        .add_attribute("claimed_periods", eligible_periods.to_string()))
+/// ## Description
+/// Removes a vesting position stored under its recipient and vesting id.
+///
+/// ## Params
+/// - **storage** is an object of type [`Storage`].
+///
+/// - **vesting_info** is an object of type [`VestingInfo`] which is the position to be removed.
+fn remove_vesting(storage: &mut dyn Storage, vesting_info: &VestingInfo) -> StdResult<()> {
+    let key = (&vesting_info.recipient, vesting_info.vesting_id);
+    if let Some(old_vesting_info) = VESTING_INFO.may_load(storage, key)? {
+        update_redistribution_weight(
+            storage,
+            old_vesting_info.redistribution_weight(),
+            Uint128::zero(),
+        )?;
+        update_validator_delegations(
+            storage,
+            &old_vesting_info.recipient,
+            old_vesting_info.vesting_id,
+            &old_vesting_info.delegations,
+            &[],
+        )?;
+    }
+    VESTING_INFO.remove(storage, key);
+    Ok(())
 }
 
 /// ## Description
-/// Claims any eligible vesting amount.
+/// Updates the amounts delegated to each validator by a vesting position, and the totals
+/// delegated to the validators, as its delegations change from `old_delegations` to
+/// `new_delegations`. The staking rewards of each changed delegation are credited to `recipient`
+/// beforehand.
 ///
 /// ## Params
-/// - **deps** is an object of type [`DepsMut`].
+/// - **storage** is an object of type [`Storage`].
 ///
-/// - **env** is an object of type [`Env`].
+/// - **recipient** is an object of type [`Addr`] which is the recipient of the vesting position.
 ///
-/// - **info** is an object of type [`MessageInfo`].
+/// - **vesting_id** is an object of type [`u64`] which is the vesting position.
 ///
-/// - **recipient** is an object of type [`String`] which the address of a protocol's recipient address.
+/// - **old_delegations** is a slice of [`Delegation`] which are the previous delegations.
 ///
-/// - **approve** is an object of type [`bool`] which is the new vesting status.
-pub fn try_approve_tollgate(
-    deps: DepsMut,
-    env: Env,
-    info: MessageInfo,
-    recipient: String,
-    approve: bool,
-) -> Result<Response, ContractError> {
-    let config: Config = CONFIG.load(deps.storage)?;
-
-    // Can only be called by master_address
-    if info.sender != config.master_address {
-        return Err(ContractError::Unauthorized {});
+/// - **new_delegations** is a slice of [`Delegation`] which are the new delegations.
+fn update_validator_delegations(
+    storage: &mut dyn Storage,
+    recipient: &Addr,
+    vesting_id: u64,
+    old_delegations: &[Delegation],
+    new_delegations: &[Delegation],
+) -> StdResult<()> {
+    let mut amounts: BTreeMap<&str, (Uint128, Uint128)> = BTreeMap::new();
+    for delegation in old_delegations {
+        amounts.entry(&delegation.validator).or_default().0 += delegation.amount;
     }
-    // Validate address and load its vesting information
-    let validated_recipient = deps.api.addr_validate(&recipient)?;
-    let mut vesting_info = VESTING_INFO.load(deps.storage, &validated_recipient)?;
-
-    // Revert if vesting for recipient is no longer active (last tollgate not approved)
-    if !vesting_info.active {
-        return Err(ContractError::VestingNotActive {});
+    for delegation in new_delegations {
+        amounts.entry(&delegation.validator).or_default().1 += delegation.amount;
     }
-
-    // Compute how many periods have passed
-    let periods_elapsed =
-        (env.block.time.seconds() - config.vesting_start_time) / SECONDS_PER_PERIOD;
-
-    // Check if the additional periods do not exceed the vesting total periods
-    // and the tollgate is less than the current time.
-    if vesting_info.approved_periods + PERIODS_PER_TOLL > vesting_info.total_periods {
-        return Err(ContractError::NoTollgateRequired {});
-    } else if vesting_info.approved_periods > periods_elapsed {
-        return Err(ContractError::NextTollgateTimeNotReached {});
+    for (validator, (old_amount, new_amount)) in amounts {
+        if old_amount == new_amount {
+            continue;
+        }
+        let mut validator_info = VALIDATORS.may_load(storage, validator)?.unwrap_or_default();
+        if let Some(delegator_info) =
+            VALIDATOR_DELEGATIONS.may_load(storage, (validator, vesting_id))?
+        {
+            add_rewards(
+                storage,
+                recipient,
+                delegator_info.pending_rewards(&validator_info),
+            )?;
+        }
+        if new_amount.is_zero() {
+            VALIDATOR_DELEGATIONS.remove(storage, (validator, vesting_id));
+        } else {
+            let delegator_info = DelegatorInfo {
+                amount: new_amount,
+                reward_indexes: validator_info.reward_indexes.clone(),
+            };
+            VALIDATOR_DELEGATIONS.save(storage, (validator, vesting_id), &delegator_info)?;
+        }
+        validator_info.total_amount = validator_info.total_amount - old_amount + new_amount;
+        VALIDATORS.save(storage, validator, &validator_info)?;
     }
+    Ok(())
+}
 
-    let mut msgs: Vec<SubMsg> = vec![];
-    // Increase the tollgate if the new approve status is true
-    // Otherwise, set the vesting to be inactive
-    if approve {
-        vesting_info.approved_periods += PERIODS_PER_TOLL;
-    } else {
-        vesting_info.active = false;
-        let claimable_amount = vesting_info.amount_per_period
-            * Uint128::from(vesting_info.approved_periods - vesting_info.last_claimed_period);
-        msgs.push(SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-            to_address: config.master_address.to_string(),
-            amount: coins(
-                (vesting_info.vested_amount - claimable_amount).into(),
-                config.denom,
-            ),
-        })));
-        vesting_info.vested_amount = claimable_amount;
+/// ## Description
+/// Credits the staking rewards accrued by the delegations of the recipient's vesting positions
+/// since they were last credited.
+///
+/// ## Params
+/// - **storage** is an object of type [`Storage`].
+///
+/// - **recipient** is an object of type [`Addr`] which is the recipient of the vesting positions.
+fn settle_rewards(storage: &mut dyn Storage, recipient: &Addr) -> StdResult<()> {
+    let delegations = VESTING_INFO
+        .prefix(recipient)
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| {
+            item.map(|(vesting_id, vesting_info)| {
+                vesting_info
+                    .delegations
+                    .into_iter()
+                    .map(|delegation| (delegation.validator, vesting_id))
+                    .collect::<Vec<(String, u64)>>()
+            })
+        })
+        .collect::<StdResult<Vec<Vec<(String, u64)>>>>()?
+        .concat();
+    for (validator, vesting_id) in delegations {
+        let validator_info = VALIDATORS.load(storage, &validator)?;
+        let mut delegator_info = VALIDATOR_DELEGATIONS.load(storage, (&validator, vesting_id))?;
+        add_rewards(
+            storage,
+            recipient,
+            delegator_info.pending_rewards(&validator_info),
+        )?;
+        delegator_info.reward_indexes = validator_info.reward_indexes;
+        VALIDATOR_DELEGATIONS.save(storage, (&validator, vesting_id), &delegator_info)?;
     }
-    VESTING_INFO.save(deps.storage, &validated_recipient, &vesting_info)?;
-    Ok(Response::new()
-        .add_submessages(msgs)
-        .add_attribute("method", "try_approve_tollgate")
-        .add_attribute("recipient", info.sender)
-        .add_attribute("vesting_status", vesting_info.active.to_string())
-        .add_attribute(
-            "approved_periods",
-            vesting_info.approved_periods.to_string(),
-        ))
+    Ok(())
 }
 
 /// ## Description
-/// Exposes all the queries available in the contract.
+/// Adds `rewards` to the staking rewards credited to `recipient`.
 ///
 /// ## Params
-/// - **deps** is an object of type [`Deps`].
+/// - **storage** is an object of type [`Storage`].
 ///
-/// - **_env** is an object of type [`Env`].
+/// - **recipient** is an object of type [`Addr`] which is the recipient of the rewards.
 ///
-/// - **msg** is an object of type [`QueryMsg`].
+/// - **rewards** is a vector of [`Coin`] which are the rewards to be credited.
+fn add_rewards(storage: &mut dyn Storage, recipient: &Addr, rewards: Vec<Coin>) -> StdResult<()> {
+    if rewards.is_empty() {
+        return Ok(());
+    }
+    REWARDS.update(storage, recipient, |credited| -> StdResult<_> {
+        let mut credited = credited.unwrap_or_default();
+        credited.extend(rewards);
+        Ok(merge_coins(credited))
+    })?;
+    Ok(())
+}
+
+/// ## Description
+/// Returns a [`ContractError::InvalidUnlockSteps`] if the custom unlock steps are empty,
+/// unlock before `start_time` or do not sum to `amount`.
 ///
-/// ## Commands
-/// - **QueryMsg::VestingInfo {
-///                 recipient,
-///             }** Returns the vesting information of the specified recipient.
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::VestingInfo { recipient } => to_binary(&query_vesting_info(deps, recipient)?),
-        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+/// ## Params
+/// - **steps** is a slice of [`UnlockStep`] which are the unlock steps to be validated.
+///
+/// - **amount** is an object of type [`Uint128`] which is the vesting amount.
+///
+/// - **start_time** is an object of type [`u64`] which is the start time of the vesting.
+fn validate_unlock_steps(
+    steps: &[UnlockStep],
+    amount: Uint128,
+    start_time: u64,
+) -> Result<(), ContractError> {
+    let sum_step_amount = steps
+        .iter()
+        .fold(Uint128::zero(), |sum, step| sum + step.amount());
+    let before_start = steps.iter().any(|step| match step {
+        UnlockStep::Time { time, .. } => *time < start_time,
+        UnlockStep::Period { .. } => false,
+    });
+    if steps.is_empty() || before_start || sum_step_amount != amount {
+        return Err(ContractError::InvalidUnlockSteps {});
     }
+    Ok(())
 }
 
 /// ## Description
-/// Returns the vesting information of the specified recipient.
+/// Returns a [`ContractError::InvalidTollgatePeriods`] if the tollgate periods are not strictly
+/// increasing or not within the vesting periods.
 ///
 /// ## Params
-/// - **deps** is an object of type [`Deps`].
+/// - **tollgate_periods** is a slice of [`u64`] which are the tollgate periods to be validated.
 ///
-/// - **recipient** is an object of type [`String`] which is the address used to query vesting information.
-fn query_vesting_info(deps: Deps, recipient: String) -> StdResult<VestingInfo> {
-    let vesting_info = VESTING_INFO.load(deps.storage, &deps.api.addr_validate(&recipient)?)?;
-    Ok(vesting_info)
+/// - **total_periods** is an object of type [`u64`] which is the total vesting periods.
+fn validate_tollgate_periods(
+    tollgate_periods: &[u64],
+    total_periods: u64,
+) -> Result<(), ContractError> {
+    let increasing = tollgate_periods.windows(2).all(|pair| pair[0] < pair[1]);
+    let within_periods = tollgate_periods
+        .iter()
+        .all(|period| *period > 0 && *period < total_periods);
+    if !increasing || !within_periods {
+        return Err(ContractError::InvalidTollgatePeriods {});
+    }
+    Ok(())
 }
 
-fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
-    let config: Config = CONFIG.load(deps.storage)?;
-    let resp = ConfigResponse {
-        master_address: config.master_address.to_string(),
-        denom: config.denom,
-        vesting_start_time: config.vesting_start_time,
-    };
+/// ## Description
+/// Returns a [`ContractError::Unauthorized`] if `addr` has not been granted with `role`.
+///
+/// ## Params
+/// - **storage** is an object of type [`Storage`].
+///
+/// - **role** is an object of type [`Role`] which is the required role.
+///
+/// - **addr** is an object of type [`Addr`] which is the address to be checked.
+fn assert_role(storage: &dyn Storage, role: Role, addr: &Addr) -> Result<(), ContractError> {
+    if !ROLES.load(storage)?.has(role, addr) {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
 
-    Ok(resp)
+/// ## Description
+/// Returns a [`ContractError::Paused`] if the contract is paused or `recipient` is frozen.
+///
+/// ## Params
+/// - **storage** is an object of type [`Storage`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **config** is an object of type [`Config`].
+///
+/// - **recipient** is an object of type [`Addr`] which is the recipient to be checked.
+fn assert_not_paused(
+    storage: &dyn Storage,
+    env: &Env,
+    config: &Config,
+    recipient: &Addr,
+) -> Result<(), ContractError> {
+    let now = env.block.time.seconds();
+    let global_pause = config.pause.as_ref();
+    let recipient_freeze = FROZEN_RECIPIENTS.may_load(storage, recipient)?;
+    if global_pause
+        .into_iter()
+        .chain(recipient_freeze.iter())
+        .any(|pause| pause.is_active(now))
+    {
+        return Err(ContractError::Paused {});
+    }
+    Ok(())
 }
 
 /// ## Description
-/// Exposes the migrate functionality in the contract.
+/// Exposes all the execute functions available in the contract.
 ///
 /// ## Params
-/// - **_deps** is an object of type [`DepsMut`].
+/// - **deps** is an object of type [`DepsMut`].
 ///
-/// - **_env** is an object of type [`Env`].
+/// - **env** is an object of type [`Env`].
 ///
-/// - **_msg** is an object of type [`MigrateMsg`].
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
-    Ok(Response::default())
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **msg** is an object of type [`ExecuteMsg`].
+///
+/// ## Commands
+/// - **ExecuteMsg::UpdateConfig { forfeit_address, forfeit_mode }** Updates the contract
+///   settings.
+///
+/// - **ExecuteMsg::GrantRole { role, address }** Grants a role to an address.
+///
+/// - **ExecuteMsg::RevokeRole { role, address }** Revokes a role from an address.
+///
+/// - **ExecuteMsg::SweepExcess { denom, to }** Sends the funds in excess of the outstanding
+///   vestings.
+///
+/// - **ExecuteMsg::ResolveIbcPayout { payout_id, delivered }** Resolves an IBC payout once its
+///   transfer is acknowledged or timed out, or prunes it once it has expired.
+///
+/// - **ExecuteMsg::ApproveTollgate { recipient, vesting_id, approve, payout }** Updates the
+///   tollgate / approve status of a recipient's vesting status.
+///
+/// - **ExecuteMsg::Pause { recipient, auto_unpause_time }** Pauses the contract or freezes a
+///   recipient.
+///
+/// - **ExecuteMsg::Unpause { recipient }** Unpauses the contract or unfreezes a recipient.
+///
+/// - **ExecuteMsg::ReclaimUnaccepted { recipient, vesting_id }** Forfeits a vesting not accepted before
+///   its deadline.
+///
+/// - **ExecuteMsg::AddVestings { vestings }** Adds new vestings funded by the sent tokens.
+///
+/// - **ExecuteMsg::TopUpVesting { recipient, vesting_id }** Tops up an active vesting with the
+///   sent tokens.
+///
+/// - **ExecuteMsg::Claim { vesting_ids, ibc }** Claims any eligible vesting amount across the
+///   sender's vesting positions, optionally paid out over IBC.
+///
+/// - **ExecuteMsg::AcceptAllocation { vesting_id }** Accepts the sender's own vesting.
+///
+/// - **ExecuteMsg::Renounce { vesting_id, keep_claimable }** Deactivates the sender's own vesting.
+///
+/// - **ExecuteMsg::SplitVesting { vesting_id, recipient_shares }** Splits the sender's own vesting
+///   among several recipients.
+///
+/// - **ExecuteMsg::MergeVestings { vesting_ids }** Merges the sender's own vestings with identical
+///   schedules.
+///
+/// - **ExecuteMsg::Delegate { vesting_id, validator, amount }** Stakes the unclaimed amount of the
+///   sender's own vesting.
+///
+/// - **ExecuteMsg::Redelegate { vesting_id, src_validator, dst_validator, amount }** Moves a
+///   delegation of the sender's own vesting.
+///
+/// - **ExecuteMsg::Undelegate { vesting_id, validator, amount }** Unbonds a delegation of the
+///   sender's own vesting.
+///
+/// - **ExecuteMsg::WithdrawRewards {}** Withdraws the staking rewards credited to the sender.
+///
+/// - **ExecuteMsg::RetryPayout {}** Sends the failed payouts credited to the sender.
+///
+/// - **ExecuteMsg::SetClaimHook { hook }** Registers a contract notified of the sender's claims.
+///
+/// - **ExecuteMsg::Vote { proposal_id, vote }** Votes on a governance proposal with the sender's
+///   delegations.
+///
+/// - **ExecuteMsg::TransferNft { recipient, token_id }** Transfers the sender's own vesting position.
+///
+/// - **ExecuteMsg::SendNft { contract, token_id, msg }** Transfers the sender's own vesting position
+///   to a contract and notifies it.
+///
+/// - **ExecuteMsg::ExpireVesting { recipient, vesting_id }** Sweeps the unclaimed amount of an expired
+///   vesting.
+///
+/// - **ExecuteMsg::SettleForfeit { recipient, vesting_id }** Handles the forfeited amount of a vesting
+///   once it is unbonded.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    // Only the funding messages take payment
+    let is_funding = matches!(
+        msg,
+        ExecuteMsg::AddVestings { .. } | ExecuteMsg::TopUpVesting { .. }
+    );
+    if !is_funding && !info.funds.is_empty() {
+        return Err(ContractError::UnexpectedFunds {});
+    }
+
+    match msg {
+        ExecuteMsg::UpdateConfig {
+            forfeit_address,
+            forfeit_mode,
+        } => try_update_config(deps, info, forfeit_address, forfeit_mode),
+        ExecuteMsg::GrantRole { role, address } => try_grant_role(deps, info, role, address),
+        ExecuteMsg::RevokeRole { role, address } => try_revoke_role(deps, info, role, address),
+        ExecuteMsg::SweepExcess { denom, to } => try_sweep_excess(deps, env, info, denom, to),
+        ExecuteMsg::ResolveIbcPayout {
+            payout_id,
+            delivered,
+        } => try_resolve_ibc_payout(deps, env, info, payout_id, delivered),
+        ExecuteMsg::ApproveTollgate {
+            recipient,
+            vesting_id,
+            approve,
+            payout,
+        } => try_approve_tollgate(
+            deps,
+            env,
+            info,
+            recipient,
+            vesting_id,
+            approve,
+            payout.unwrap_or(false),
+        ),
+        ExecuteMsg::ReclaimUnaccepted {
+            recipient,
+            vesting_id,
+        } => try_reclaim_unaccepted(deps, env, info, recipient, vesting_id),
+        ExecuteMsg::Pause {
+            recipient,
+            auto_unpause_time,
+        } => try_pause(deps, env, info, recipient, auto_unpause_time),
+        ExecuteMsg::Unpause { recipient } => try_unpause(deps, env, info, recipient),
+        ExecuteMsg::AddVestings { vestings } => try_add_vestings(deps, env, info, vestings),
+        ExecuteMsg::TopUpVesting {
+            recipient,
+            vesting_id,
+        } => try_top_up_vesting(deps, info, recipient, vesting_id),
+        ExecuteMsg::Claim { vesting_ids, ibc } => try_claim(deps, env, info, vesting_ids, ibc),
+        ExecuteMsg::AcceptAllocation { vesting_id } => {
+            try_accept_allocation(deps, env, info, vesting_id)
+        }
+        ExecuteMsg::Renounce {
+            vesting_id,
+            keep_claimable,
+        } => try_renounce(deps, env, info, vesting_id, keep_claimable),
+        ExecuteMsg::SplitVesting {
+            vesting_id,
+            recipient_shares,
+        } => try_split_vesting(deps, env, info, vesting_id, recipient_shares),
+        ExecuteMsg::MergeVestings { vesting_ids } => {
+            try_merge_vestings(deps, env, info, vesting_ids)
+        }
+        ExecuteMsg::Delegate {
+            vesting_id,
+            validator,
+            amount,
+        } => try_delegate(deps, env, info, vesting_id, validator, amount),
+        ExecuteMsg::Redelegate {
+            vesting_id,
+            src_validator,
+            dst_validator,
+            amount,
+        } => try_redelegate(
+            deps,
+            env,
+            info,
+            vesting_id,
+            src_validator,
+            dst_validator,
+            amount,
+        ),
+        ExecuteMsg::Undelegate {
+            vesting_id,
+            validator,
+            amount,
+        } => try_undelegate(deps, env, info, vesting_id, validator, amount),
+        ExecuteMsg::WithdrawRewards {} => try_withdraw_rewards(deps, env, info),
+        ExecuteMsg::RetryPayout {} => try_retry_payout(deps, env, info),
+        ExecuteMsg::SetClaimHook { hook } => try_set_claim_hook(deps, info, hook),
+        ExecuteMsg::Vote { proposal_id, vote } => try_vote(deps, env, info, proposal_id, vote),
+        ExecuteMsg::TransferNft {
+            recipient,
+            token_id,
+        } => try_transfer_nft(deps, env, info, recipient, token_id),
+        ExecuteMsg::SendNft {
+            contract,
+            token_id,
+            msg,
+        } => try_send_nft(deps, env, info, contract, token_id, msg),
+        ExecuteMsg::ExpireVesting {
+            recipient,
+            vesting_id,
+        } => try_expire_vesting(deps, env, info, recipient, vesting_id),
+        ExecuteMsg::SettleForfeit {
+            recipient,
+            vesting_id,
+        } => try_settle_forfeit(deps, env, info, recipient, vesting_id),
+    }
+}
+
+/// ## Description
+/// Returns the current reading of `clock`, i.e. the block time in seconds or the block height.
+///
+/// ## Params
+/// - **clock** is an object of type [`Clock`] which measures the vesting schedules.
+///
+/// - **env** is an object of type [`Env`].
+fn clock_now(clock: &Clock, env: &Env) -> u64 {
+    match clock {
+        Clock::Time | Clock::CalendarMonth => env.block.time.seconds(),
+        Clock::Height { .. } => env.block.height,
+    }
+}
+
+/// ## Description
+/// Returns the reading of `clock` at which `periods` periods have passed since `start_time`.
+///
+/// ## Params
+/// - **clock** is an object of type [`Clock`] which measures the vesting schedules.
+///
+/// - **start_time** is an object of type [`u64`] which is the start time of the vesting.
+///
+/// - **periods** is an object of type [`u64`] which is the number of periods.
+fn period_start(clock: &Clock, start_time: u64, periods: u64) -> u64 {
+    match clock {
+        Clock::Time => start_time.saturating_add(periods.saturating_mul(SECONDS_PER_PERIOD)),
+        Clock::Height { blocks_per_period } => {
+            start_time.saturating_add(periods.saturating_mul(*blocks_per_period))
+        }
+        Clock::CalendarMonth => add_months(start_time, periods),
+    }
+}
+
+/// ## Description
+/// Returns the number of whole periods passed since `start_time` at `time`.
+///
+/// ## Params
+/// - **clock** is an object of type [`Clock`] which measures the vesting schedules.
+///
+/// - **start_time** is an object of type [`u64`] which is the start time of the vesting.
+///
+/// - **time** is an object of type [`u64`] which is the current reading of the clock.
+fn periods_elapsed(clock: &Clock, start_time: u64, time: u64) -> u64 {
+    match clock {
+        Clock::Time => time.saturating_sub(start_time) / SECONDS_PER_PERIOD,
+        Clock::Height { blocks_per_period } => time.saturating_sub(start_time) / *blocks_per_period,
+        Clock::CalendarMonth => {
+            if time <= start_time {
+                return 0u64;
+            }
+            let (start_year, start_month, _) = civil_from_days(start_time / SECONDS_PER_DAY);
+            let (year, month, _) = civil_from_days(time / SECONDS_PER_DAY);
+            let months = ((year - start_year) * 12 + month as i64 - start_month as i64) as u64;
+            // The month of `time` only counts once its unlock day and time of day are reached
+            if add_months(start_time, months) > time {
+                months - 1
+            } else {
+                months
+            }
+        }
+    }
+}
+
+/// ## Description
+/// Returns the timestamp `months` calendar months after `time`, on the same day of the month
+/// and time of day (UTC). Days past the end of a shorter month fall on its last day.
+///
+/// ## Params
+/// - **time** is an object of type [`u64`] which is the timestamp in seconds.
+///
+/// - **months** is an object of type [`u64`] which is the number of months to be added.
+fn add_months(time: u64, months: u64) -> u64 {
+    let (year, month, day) = civil_from_days(time / SECONDS_PER_DAY);
+    let month_index = month as i128 - 1 + months as i128;
+    let year = year as i128 + month_index / 12;
+    let month = (month_index % 12) as u32 + 1;
+    let day = std::cmp::min(day, days_in_month(year, month));
+    let seconds = days_from_civil(year, month, day) * SECONDS_PER_DAY as i128
+        + (time % SECONDS_PER_DAY) as i128;
+    std::cmp::min(seconds, u64::MAX as i128) as u64
+}
+
+/// ## Description
+/// Returns the (year, month, day) of the UTC date `days` days after 1970-01-01.
+///
+/// ## Params
+/// - **days** is an object of type [`u64`] which is the number of days since the Unix epoch.
+fn civil_from_days(days: u64) -> (i64, u32, u32) {
+    // Shift the epoch to 0000-03-01 so that leap days fall at the end of each year
+    let days = days as i64 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// ## Description
+/// Returns the number of days from 1970-01-01 to the UTC date (`year`, `month`, `day`).
+///
+/// ## Params
+/// - **year** is an object of type [`i128`].
+///
+/// - **month** is an object of type [`u32`] from 1 to 12.
+///
+/// - **day** is an object of type [`u32`] from 1.
+fn days_from_civil(year: i128, month: u32, day: u32) -> i128 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let shifted_month = (month as i128 + 9) % 12;
+    let day_of_year = (153 * shifted_month + 2) / 5 + day as i128 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// ## Description
+/// Returns the number of days in `month` of `year`.
+///
+/// ## Params
+/// - **year** is an object of type [`i128`].
+///
+/// - **month** is an object of type [`u32`] from 1 to 12.
+fn days_in_month(year: i128, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// ## Description
+/// Returns the amount unlocked by the schedule of a vesting at `time`, capped by its approved periods.
+///
+/// ## Params
+/// - **vesting_info** is an object of type [`VestingInfo`] which is the vesting to be computed.
+///
+/// - **clock** is an object of type [`Clock`] which measures the vesting schedules.
+///
+/// - **time** is an object of type [`u64`] which is the current reading of the clock.
+fn unlocked_amount(vesting_info: &VestingInfo, clock: &Clock, time: u64) -> Uint128 {
+    let start_time = vesting_info.start_time;
+    // Tollgates cut off the schedule at the approved periods
+    let unlocked_periods = std::cmp::min(vesting_info.approved_periods, vesting_info.total_periods);
+    match &vesting_info.mode {
+        VestingMode::Step => {
+            let eligible_periods =
+                std::cmp::min(periods_elapsed(clock, start_time, time), unlocked_periods);
+            vesting_info.amount_per_period * Uint128::from(eligible_periods)
+        }
+        VestingMode::Linear => {
+            // Rounds down until the final second, when the total amount is unlocked exactly
+            let eligible_seconds =
+                std::cmp::min(time, period_start(clock, start_time, unlocked_periods))
+                    .saturating_sub(start_time);
+            let total_seconds =
+                period_start(clock, start_time, vesting_info.total_periods) - start_time;
+            vesting_info
+                .total_amount
+                .multiply_ratio(eligible_seconds, total_seconds)
+        }
+        VestingMode::Custom { steps } => steps
+            .iter()
+            .filter(|step| {
+                step_period(step, clock, start_time) <= unlocked_periods
+                    && step_time(step, clock, start_time) <= time
+            })
+            .fold(Uint128::zero(), |sum, step| sum + step.amount()),
+        // Each approved milestone unlocks its tranche regardless of time
+        VestingMode::Milestones { tranches } => tranches
+            .iter()
+            .take(unlocked_periods as usize)
+            .fold(Uint128::zero(), |sum, tranche| sum + tranche),
+    }
+}
+
+/// ## Description
+/// Returns whether a vesting unlocks by milestones only, with no time component.
+///
+/// ## Params
+/// - **vesting_info** is an object of type [`VestingInfo`] which is the vesting to be checked.
+fn is_milestone_vesting(vesting_info: &VestingInfo) -> bool {
+    matches!(vesting_info.mode, VestingMode::Milestones { .. })
+}
+
+/// ## Description
+/// Returns the period in which an unlock step unlocks, i.e. the number of periods since the start
+/// time that must pass and be approved for it to unlock.
+///
+/// ## Params
+/// - **step** is an object of type [`UnlockStep`] which is the step to be computed.
+///
+/// - **clock** is an object of type [`Clock`] which measures the vesting schedules.
+///
+/// - **start_time** is an object of type [`u64`] which is the start time of the vesting.
+fn step_period(step: &UnlockStep, clock: &Clock, start_time: u64) -> u64 {
+    match step {
+        UnlockStep::Period { period, .. } => *period,
+        UnlockStep::Time { time, .. } => {
+            // Rounds up as the step unlocks within the period
+            let periods = periods_elapsed(clock, start_time, *time);
+            if period_start(clock, start_time, periods) < *time {
+                periods + 1
+            } else {
+                periods
+            }
+        }
+    }
+}
+
+/// ## Description
+/// Returns the reading of the clock at which an unlock step unlocks, ignoring tollgates and cliffs.
+///
+/// ## Params
+/// - **step** is an object of type [`UnlockStep`] which is the step to be computed.
+///
+/// - **clock** is an object of type [`Clock`] which measures the vesting schedules.
+///
+/// - **start_time** is an object of type [`u64`] which is the start time of the vesting.
+fn step_time(step: &UnlockStep, clock: &Clock, start_time: u64) -> u64 {
+    match step {
+        UnlockStep::Period { period, .. } => period_start(clock, start_time, *period),
+        UnlockStep::Time { time, .. } => *time,
+    }
+}
+
+/// ## Description
+/// Returns the total eligible periods at `time`, including claimed and unclaimed periods,
+/// and the amount which can be claimed for them.
+///
+/// ## Params
+/// - **vesting_info** is an object of type [`VestingInfo`] which is the vesting to be computed.
+///
+/// - **clock** is an object of type [`Clock`] which measures the vesting schedules.
+///
+/// - **time** is an object of type [`u64`] which is the current reading of the clock.
+fn compute_claimable(vesting_info: &VestingInfo, clock: &Clock, time: u64) -> (u64, Uint128) {
+    // Nothing is eligible before the cliff ends
+    if time
+        < vesting_info
+            .start_time
+            .saturating_add(vesting_info.cliff_duration)
+    {
+        return (0u64, Uint128::zero());
+    }
+    // Compute the number of periods has passed since the vesting start,
+    // which is not limiting for milestones
+    let periods_since_start = if is_milestone_vesting(vesting_info) {
+        u64::MAX
+    } else {
+        periods_elapsed(clock, vesting_info.start_time, time)
+    };
+    // Calculate the total eligible periods -- including claimed and unclaimed periods
+    let eligible_periods = std::cmp::min(
+        periods_since_start,
+        std::cmp::min(vesting_info.approved_periods, vesting_info.total_periods),
+    );
+    // Compute claimable amounts according to the unlocked amount, including any shortfall
+    // of the already claimed periods left by top ups, up to what is left after a forfeiture
+    let claimable_amount = std::cmp::min(
+        unlocked_amount(vesting_info, clock, time).saturating_sub(vesting_info.claimed_amount),
+        vesting_info.vested_amount,
+    );
+    (eligible_periods, claimable_amount)
+}
+
+/// ## Description
+/// Returns the time after which the unclaimed amount of a vesting expires, if claim expiry is enabled.
+/// Disapproved vestings expire relative to their last approved period.
+///
+/// ## Params
+/// - **config** is an object of type [`Config`].
+///
+/// - **vesting_info** is an object of type [`VestingInfo`] which is the vesting to be computed.
+fn claim_expiry_time(config: &Config, vesting_info: &VestingInfo) -> Option<u64> {
+    // Milestones have no final period in time
+    if is_milestone_vesting(vesting_info) {
+        return None;
+    }
+    let final_period = if vesting_info.active {
+        vesting_info.total_periods
+    } else {
+        std::cmp::min(vesting_info.approved_periods, vesting_info.total_periods)
+    };
+    config.claim_expiry_duration.map(|claim_expiry_duration| {
+        period_start(&config.clock, vesting_info.start_time, final_period)
+            .saturating_add(claim_expiry_duration)
+    })
+}
+
+/// ## Description
+/// Updates the tollgate / approve status of a recipient's vesting status.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **vesting_ids** is an [`Option`] of type [`Vec<u64>`] which are the vesting positions to claim from.
+pub fn try_claim(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    vesting_ids: Option<Vec<u64>>,
+    ibc: Option<IbcClaim>,
+) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    assert_not_paused(deps.storage, &env, &config, &info.sender)?;
+    let now = clock_now(&config.clock, &env);
+    if ibc.as_ref().map_or(false, |ibc| {
+        ibc.channel_id.is_empty() || ibc.remote_address.is_empty()
+    }) {
+        return Err(ContractError::InvalidIbcClaim {});
+    }
+
+    // Positions which cannot be claimed fail the claim only if they are explicitly specified
+    let explicit = vesting_ids.is_some();
+    let vesting_ids = match vesting_ids {
+        Some(mut vesting_ids) => {
+            vesting_ids.sort_unstable();
+            vesting_ids.dedup();
+            vesting_ids
+        }
+        None => VESTING_INFO
+            .prefix(&info.sender)
+            .keys(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<u64>>>()?,
+    };
+
+    let mut res = Response::new()
+        .add_attribute("method", "try_claim")
+        .add_attribute("recipient", info.sender.clone());
+    let mut total_claimed_amount = Uint128::zero();
+    let mut claimed_positions: Vec<ClaimedPosition> = vec![];
+    let mut claimed_coins: Vec<(u64, Coin)> = vec![];
+    let mut skipped_err = ContractError::NoClaimable {};
+    for vesting_id in vesting_ids {
+        // Positions are loaded one at a time, as reconciling one may update the others
+        let vesting_info = load_vesting(deps.storage, &info.sender, Some(vesting_id))?;
+        let mut vesting_info = reconcile_vesting(deps.storage, &deps.querier, &env, vesting_info)?;
+        let unclaimable_err = if vesting_info.acceptance_deadline.is_some() {
+            Some(ContractError::PendingAcceptance {})
+        } else if claim_expiry_time(&config, &vesting_info)
+            .map_or(false, |expiry_time| now >= expiry_time)
+        {
+            Some(ContractError::VestingExpired {})
+        } else {
+            None
+        };
+        if let Some(err) = unclaimable_err {
+            if explicit {
+                return Err(err);
+            }
+            skipped_err = err;
+            continue;
+        }
+
+        let (eligible_periods, claimable_amount) =
+            compute_claimable(&vesting_info, &config.clock, now);
+        if claimable_amount.is_zero() {
+            continue;
+        }
+        // Only the amount held by the contract can be paid out, the rest being staked
+        let claimable_amount = std::cmp::min(
+            claimable_amount,
+            vesting_info.payable_amount(env.block.time.seconds()),
+        );
+        if claimable_amount.is_zero() {
+            skipped_err = ContractError::ClaimableStaked {};
+            continue;
+        }
+
+        // Update recipient's vesting info
+        claimed_coins.extend(
+            vesting_info
+                .claim(eligible_periods, claimable_amount)
+                .into_iter()
+                .chain(std::iter::once(Coin::new(
+                    claimable_amount.u128(),
+                    config.denom.clone(),
+                )))
+                .map(|coin| (vesting_id, coin)),
+        );
+        save_vesting(deps.storage, &vesting_info)?;
+
+        total_claimed_amount += claimable_amount;
+        claimed_positions.push(ClaimedPosition {
+            vesting_id,
+            periods: eligible_periods,
+        });
+        res = res
+            .add_attribute("vesting_id", vesting_info.vesting_id.to_string())
+            .add_attribute("claimed_periods", eligible_periods.to_string());
+    }
+    if total_claimed_amount.is_zero() {
+        return Err(skipped_err);
+    }
+
+    let ibc = match ibc {
+        Some(ibc) => ibc,
+        None => {
+            // All due coins are sent at once, along with a notification if the recipient
+            // registered a claim hook
+            let amount = merge_coins(claimed_coins.into_iter().map(|(_, coin)| coin).collect());
+            let msg = match CLAIM_HOOKS.may_load(deps.storage, &info.sender)? {
+                Some(hook) => {
+                    let hook_msg = ClaimHookExecuteMsg::VestingClaimHook(VestingClaimHook {
+                        amount: amount.clone(),
+                        positions: claimed_positions,
+                    });
+                    res = res.add_attribute("hook", hook.clone());
+                    payout_submsg(
+                        deps.storage,
+                        &info.sender,
+                        amount.clone(),
+                        CosmosMsg::Wasm(WasmMsg::Execute {
+                            contract_addr: hook.to_string(),
+                            msg: to_binary(&hook_msg)?,
+                            funds: amount,
+                        }),
+                    )?
+                }
+                None => payout_msg(deps.storage, &info.sender, amount)?,
+            };
+            return Ok(res
+                .add_submessage(msg)
+                .add_attribute("claimed_amount", total_claimed_amount));
+        }
+    };
+
+    // Each due coin is transferred on its own, and restored if its transfer fails
+    let timeout = env.block.time.seconds() + ibc.timeout.unwrap_or(IBC_TIMEOUT_SECONDS);
+    let mut payout_id = PAYOUT_ID.may_load(deps.storage)?.unwrap_or_default();
+    let amounts = merge_coins(claimed_coins.iter().map(|(_, coin)| coin.clone()).collect());
+    for amount in amounts {
+        payout_id += 1;
+        let payout = IbcPayout {
+            payout_id,
+            recipient: info.sender.clone(),
+            channel_id: ibc.channel_id.clone(),
+            remote_address: ibc.remote_address.clone(),
+            claims: claimed_coins
+                .iter()
+                .filter(|(_, coin)| coin.denom == amount.denom && !coin.amount.is_zero())
+                .map(|(vesting_id, coin)| ClaimedAmount {
+                    vesting_id: *vesting_id,
+                    amount: coin.amount,
+                })
+                .collect(),
+            amount,
+            timeout,
+        };
+        save_ibc_payout(deps.storage, &payout)?;
+        res = res
+            .add_submessage(SubMsg::reply_on_error(
+                CosmosMsg::Ibc(IbcMsg::Transfer {
+                    channel_id: payout.channel_id,
+                    to_address: payout.remote_address,
+                    amount: payout.amount,
+                    timeout: IbcTimeout::with_timestamp(Timestamp::from_seconds(timeout)),
+                }),
+                payout_id,
+            ))
+            .add_attribute("payout_id", payout_id.to_string());
+    }
+    PAYOUT_ID.save(deps.storage, &payout_id)?;
+
+    Ok(res
+        .add_attribute("channel_id", ibc.channel_id)
+        .add_attribute("remote_address", ibc.remote_address)
+        .add_attribute("claimed_amount", total_claimed_amount))
+}
+
+/// ## Description
+/// Restores the claims of an IBC payout whose transfer failed as unclaimed, in the vesting
+/// positions they were claimed from, and removes the payout.
+///
+/// ## Params
+/// - **storage** is an object of type [`Storage`].
+///
+/// - **payout** is an object of type [`IbcPayout`] which is the payout to be restored.
+fn restore_ibc_payout(storage: &mut dyn Storage, payout: &IbcPayout) -> StdResult<()> {
+    let config: Config = CONFIG.load(storage)?;
+    for claim in &payout.claims {
+        // The position may have been transferred since the claim
+        let recipient = VESTING_RECIPIENT.load(storage, claim.vesting_id)?;
+        let mut vesting_info = VESTING_INFO.load(storage, (&recipient, claim.vesting_id))?;
+        settle_redistribution(storage, &mut vesting_info)?;
+        vesting_info.unclaim(
+            &Coin::new(claim.amount.u128(), payout.amount.denom.clone()),
+            &config.denom,
+        );
+        save_vesting(storage, &vesting_info)?;
+    }
+    remove_ibc_payout(storage, payout)
+}
+
+/// ## Description
+/// Stores an IBC payout, indexed by the vesting positions it was claimed from, and adds it to the
+/// total of unresolved payouts of its denom.
+///
+/// ## Params
+/// - **storage** is an object of type [`Storage`].
+///
+/// - **payout** is an object of type [`IbcPayout`] which is the payout to be stored.
+fn save_ibc_payout(storage: &mut dyn Storage, payout: &IbcPayout) -> StdResult<()> {
+    IBC_PAYOUTS.save(storage, payout.payout_id, payout)?;
+    for claim in &payout.claims {
+        IBC_PAYOUT_POSITIONS.save(storage, (claim.vesting_id, payout.payout_id), &Empty {})?;
+    }
+    let denom = payout.amount.denom.as_str();
+    let total = IBC_PAYOUT_TOTALS
+        .may_load(storage, denom)?
+        .unwrap_or_default();
+    IBC_PAYOUT_TOTALS.save(storage, denom, &(total + payout.amount.amount))
+}
+
+/// ## Description
+/// Removes a resolved IBC payout along with its index entries and its part of the total of
+/// unresolved payouts of its denom.
+///
+/// ## Params
+/// - **storage** is an object of type [`Storage`].
+///
+/// - **payout** is an object of type [`IbcPayout`] which is the payout to be removed.
+fn remove_ibc_payout(storage: &mut dyn Storage, payout: &IbcPayout) -> StdResult<()> {
+    IBC_PAYOUTS.remove(storage, payout.payout_id);
+    for claim in &payout.claims {
+        IBC_PAYOUT_POSITIONS.remove(storage, (claim.vesting_id, payout.payout_id));
+    }
+    let denom = payout.amount.denom.as_str();
+    let total = IBC_PAYOUT_TOTALS
+        .may_load(storage, denom)?
+        .unwrap_or_default();
+    IBC_PAYOUT_TOTALS.save(storage, denom, &(total - payout.amount.amount))
+}
+
+/// ## Description
+/// Returns the message paying out `amount` to `recipient`, which is credited to the recipient
+/// if the payout fails, e.g. as the recipient is a blocked module address.
+///
+/// ## Params
+/// - **storage** is an object of type [`Storage`].
+///
+/// - **recipient** is an object of type [`Addr`] which is the recipient of the payout.
+///
+/// - **amount** is a [`Vec`] of [`Coin`] which are the coins paid out.
+fn payout_msg(storage: &mut dyn Storage, recipient: &Addr, amount: Vec<Coin>) -> StdResult<SubMsg> {
+    let msg = CosmosMsg::Bank(BankMsg::Send {
+        to_address: recipient.to_string(),
+        amount: amount.clone(),
+    });
+    payout_submsg(storage, recipient, amount, msg)
+}
+
+/// ## Description
+/// Returns `msg` sending a payout of `amount` to `recipient`, or to a contract on its behalf,
+/// which is credited to the recipient if it fails.
+///
+/// ## Params
+/// - **storage** is an object of type [`Storage`].
+///
+/// - **recipient** is an object of type [`Addr`] which is the recipient of the payout.
+///
+/// - **amount** is a [`Vec`] of [`Coin`] which are the coins paid out.
+///
+/// - **msg** is an object of type [`CosmosMsg`] which sends the coins.
+fn payout_submsg(
+    storage: &mut dyn Storage,
+    recipient: &Addr,
+    amount: Vec<Coin>,
+    msg: CosmosMsg,
+) -> StdResult<SubMsg> {
+    // A single payout is sent by each message, so the last one is the one replied to
+    PAYOUT.save(
+        storage,
+        &Payout {
+            recipient: recipient.clone(),
+            amount,
+        },
+    )?;
+    Ok(SubMsg::reply_on_error(msg, PAYOUT_REPLY_ID))
+}
+
+/// ## Description
+/// Registers a contract to be sent the sender's claims along with a [`VestingClaimHook`]
+/// notification, instead of a bank send, or removes it if not specified.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **hook** is an [`Option`] of type [`String`] which is the contract notified of the claims.
+pub fn try_set_claim_hook(
+    deps: DepsMut,
+    info: MessageInfo,
+    hook: Option<String>,
+) -> Result<Response, ContractError> {
+    let hook = match hook {
+        Some(hook) => {
+            let validated_hook = deps.api.addr_validate(&hook)?;
+            // Only a contract can handle the notification
+            deps.querier
+                .query::<cosmwasm_std::ContractInfoResponse>(&QueryRequest::Wasm(
+                    WasmQuery::ContractInfo {
+                        contract_addr: validated_hook.to_string(),
+                    },
+                ))
+                .map_err(|_| ContractError::InvalidClaimHook { hook })?;
+            CLAIM_HOOKS.save(deps.storage, &info.sender, &validated_hook)?;
+            validated_hook.to_string()
+        }
+        None => {
+            CLAIM_HOOKS.remove(deps.storage, &info.sender);
+            "none".to_string()
+        }
+    };
+
+    Ok(Response::new()
+        .add_attribute("method", "try_set_claim_hook")
+        .add_attribute("recipient", info.sender)
+        .add_attribute("hook", hook))
+}
+
+/// ## Description
+/// Handles the failure of a payout. A failed bank payout is credited to its recipient to be
+/// retried, while the claims of a failed IBC payout are restored as unclaimed.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **_env** is an object of type [`Env`].
+///
+/// - **msg** is an object of type [`Reply`] which is the result of the payout.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    let err = match msg.result {
+        SubMsgResult::Ok(_) => return Ok(Response::new()),
+        SubMsgResult::Err(err) => err,
+    };
+
+    if msg.id == PAYOUT_REPLY_ID {
+        let payout = PAYOUT.load(deps.storage)?;
+        let mut credits = CREDITS
+            .may_load(deps.storage, &payout.recipient)?
+            .unwrap_or_default();
+        credits.extend(payout.amount.iter().cloned());
+        CREDITS.save(deps.storage, &payout.recipient, &merge_coins(credits))?;
+        let amount_str: Vec<String> = payout.amount.iter().map(|coin| coin.to_string()).collect();
+        return Ok(Response::new()
+            .add_attribute("method", "reply")
+            .add_attribute("recipient", payout.recipient)
+            .add_attribute("credited_amount", amount_str.join(","))
+            .add_attribute("error", err));
+    }
+
+    let payout = IBC_PAYOUTS.load(deps.storage, msg.id)?;
+    restore_ibc_payout(deps.storage, &payout)?;
+    Ok(Response::new()
+        .add_attribute("method", "reply")
+        .add_attribute("payout_id", msg.id.to_string())
+        .add_attribute("recipient", payout.recipient)
+        .add_attribute("error", err))
+}
+
+/// ## Description
+/// Sends the failed payouts credited to the sender, which are credited again if the payout fails.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+pub fn try_retry_payout(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    assert_not_paused(deps.storage, &env, &config, &info.sender)?;
+
+    let credits = CREDITS
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    if credits.is_empty() {
+        return Err(ContractError::NoCredits {});
+    }
+    CREDITS.remove(deps.storage, &info.sender);
+
+    let credits_str: Vec<String> = credits.iter().map(|coin| coin.to_string()).collect();
+    Ok(Response::new()
+        .add_submessage(payout_msg(deps.storage, &info.sender, credits)?)
+        .add_attribute("method", "try_retry_payout")
+        .add_attribute("recipient", info.sender)
+        .add_attribute("amount", credits_str.join(",")))
+}
+
+/// ## Description
+/// Resolves an IBC payout once its transfer is acknowledged or timed out, as the contract is not
+/// notified of either. A delivered payout is dropped, while the claims of a refunded payout are
+/// restored as unclaimed, provided the contract holds the refunded coin. Only an admin may resolve
+/// a payout, while anyone may prune a payout left unresolved for [`IBC_PAYOUT_EXPIRY_SECONDS`]
+/// after its timeout as delivered.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **payout_id** is an object of type [`u64`] which is the id of the payout.
+///
+/// - **delivered** is an object of type [`bool`] which is whether the transfer was delivered.
+pub fn try_resolve_ibc_payout(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    payout_id: u64,
+    delivered: bool,
+) -> Result<Response, ContractError> {
+    let payout = IBC_PAYOUTS.load(deps.storage, payout_id)?;
+    // Can be called by an admin, or by anyone to prune a payout left unresolved long past its
+    // timeout, which can only be dropped as delivered
+    let expired = env.block.time.seconds() >= payout.timeout + IBC_PAYOUT_EXPIRY_SECONDS;
+    if !(ROLES.load(deps.storage)?.has(Role::Admin, &info.sender) || (delivered && expired)) {
+        return Err(ContractError::Unauthorized {});
+    }
+    let config: Config = CONFIG.load(deps.storage)?;
+    assert_not_paused(deps.storage, &env, &config, &payout.recipient)?;
+
+    if delivered {
+        remove_ibc_payout(deps.storage, &payout)?;
+    } else {
+        // The refunded coin is held on top of everything else outstanding besides this payout
+        let balance = deps
+            .querier
+            .query_balance(&env.contract.address, payout.amount.denom.clone())?
+            .amount;
+        let outstanding = outstanding_amount(deps.storage, &env, &config, &payout.amount.denom)?;
+        let other_outstanding = outstanding
+            .checked_sub(payout.amount.amount)
+            .map_err(StdError::from)?;
+        if balance.saturating_sub(other_outstanding) < payout.amount.amount {
+            return Err(ContractError::IbcPayoutNotRefunded { payout_id });
+        }
+        restore_ibc_payout(deps.storage, &payout)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "try_resolve_ibc_payout")
+        .add_attribute("payout_id", payout_id.to_string())
+        .add_attribute("recipient", payout.recipient)
+        .add_attribute("delivered", delivered.to_string()))
+}
+
+/// ## Description
+/// Claims any eligible vesting amount.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **recipient** is an object of type [`String`] which the address of a protocol's recipient address.
+///
+/// - **vesting_id** is an [`Option`] of type [`u64`] which is the vesting position of the recipient.
+///
+/// - **approve** is an object of type [`bool`] which is the new vesting status.
+///
+/// - **payout** is an object of type [`bool`] which is whether to pay out the claimable amount on approval.
+pub fn try_approve_tollgate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: String,
+    vesting_id: Option<u64>,
+    approve: bool,
+    payout: bool,
+) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+
+    // Can only be called by a reviewer
+    assert_role(deps.storage, Role::Reviewer, &info.sender)?;
+    // Validate address and load its vesting information
+    let validated_recipient = deps.api.addr_validate(&recipient)?;
+    assert_not_paused(deps.storage, &env, &config, &validated_recipient)?;
+    let vesting_info = load_vesting(deps.storage, &validated_recipient, vesting_id)?;
+    let mut vesting_info = reconcile_vesting(deps.storage, &deps.querier, &env, vesting_info)?;
+
+    // Revert if vesting for recipient is no longer active (last tollgate not approved)
+    if !vesting_info.active {
+        return Err(ContractError::VestingNotActive {});
+    }
+
+    // Compute how many periods have passed since the vesting start
+    let periods_elapsed = periods_elapsed(
+        &config.clock,
+        vesting_info.start_time,
+        clock_now(&config.clock, &env),
+    );
+
+    // Check if the additional periods do not exceed the vesting total periods
+    // and the tollgate is less than the current time.
+    let next_approved_periods = match &vesting_info.tollgate_periods {
+        // Each milestone is a tollgate
+        _ if is_milestone_vesting(&vesting_info) => Some(vesting_info.approved_periods + 1)
+            .filter(|period| *period <= vesting_info.total_periods),
+        // Tollgates every `PERIODS_PER_TOLL` periods by default, and at the end of the vesting
+        None if vesting_info.approved_periods < vesting_info.total_periods => Some(std::cmp::min(
+            vesting_info.approved_periods + PERIODS_PER_TOLL,
+            vesting_info.total_periods,
+        )),
+        None => None,
+        // Otherwise, up to the next configured tollgate or the end of the vesting
+        Some(tollgate_periods) if vesting_info.approved_periods < vesting_info.total_periods => {
+            tollgate_periods
+                .iter()
+                .copied()
+                .find(|period| *period > vesting_info.approved_periods)
+                .or(Some(vesting_info.total_periods))
+        }
+        Some(_) => None,
+    };
+    let next_approved_periods = match next_approved_periods {
+        None => return Err(ContractError::NoTollgateRequired {}),
+        // Milestones do not wait for any time
+        Some(_)
+            if !is_milestone_vesting(&vesting_info)
+                && vesting_info.approved_periods > periods_elapsed =>
+        {
+            return Err(ContractError::NextTollgateTimeNotReached {})
+        }
+        Some(next_approved_periods) => next_approved_periods,
+    };
+
+    let mut msgs: Vec<SubMsg> = vec![];
+    let mut forfeited_amount = Uint128::zero();
+    // Increase the tollgate if the new approve status is true
+    // Otherwise, set the vesting to be inactive
+    if approve {
+        vesting_info.approved_periods = next_approved_periods;
+
+        // Pay out anything claimable right away if ordered, so that the recipient does not
+        // need a separate claim
+        if payout {
+            if vesting_info.acceptance_deadline.is_some() {
+                return Err(ContractError::PendingAcceptance {});
+            }
+            let (eligible_periods, claimable_amount) =
+                compute_claimable(&vesting_info, &config.clock, clock_now(&config.clock, &env));
+            // Any staked part remains claimable once unbonded
+            let claimable_amount = std::cmp::min(
+                claimable_amount,
+                vesting_info.payable_amount(env.block.time.seconds()),
+            );
+            if !claimable_amount.is_zero() {
+                let mut claimed_coins = vesting_info.claim(eligible_periods, claimable_amount);
+                claimed_coins.push(Coin::new(claimable_amount.u128(), config.denom.clone()));
+                msgs.push(payout_msg(
+                    deps.storage,
+                    &vesting_info.recipient,
+                    merge_coins(claimed_coins),
+                )?);
+            }
+        }
+    } else {
+        vesting_info.active = false;
+        // Everything unlocked up to the approved periods remains claimable, as far as it has
+        // not been lost to slashing
+        let claimable_amount = std::cmp::min(
+            unlocked_amount(&vesting_info, &config.clock, u64::MAX)
+                .saturating_sub(vesting_info.claimed_amount),
+            vesting_info.vested_amount,
+        );
+        forfeited_amount = vesting_info.vested_amount - claimable_amount;
+        vesting_info.vested_amount = claimable_amount;
+    }
+
+    let forfeit_res = forfeit_vesting(
+        deps.storage,
+        &deps.querier,
+        &env,
+        &config,
+        &mut vesting_info,
+        forfeited_amount,
+    )?;
+    Ok(Response::new()
+        .add_submessages(msgs)
+        .add_submessages(forfeit_res.messages)
+        .add_attribute("method", "try_approve_tollgate")
+        .add_attribute("recipient", info.sender)
+        .add_attribute("vesting_id", vesting_info.vesting_id.to_string())
+        .add_attributes(forfeit_res.attributes)
+        .add_attribute("vesting_status", vesting_info.active.to_string())
+        .add_attribute(
+            "approved_periods",
+            vesting_info.approved_periods.to_string(),
+        ))
+}
+
+/// ## Description
+/// Accepts the sender's own vesting which is pending acceptance.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **vesting_id** is an [`Option`] of type [`u64`] which is the vesting position of the sender.
+pub fn try_accept_allocation(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    vesting_id: Option<u64>,
+) -> Result<Response, ContractError> {
+    let mut vesting_info = load_vesting(deps.storage, &info.sender, vesting_id)?;
+
+    match vesting_info.acceptance_deadline {
+        None => return Err(ContractError::NotPendingAcceptance {}),
+        Some(deadline) if env.block.time.seconds() >= deadline => {
+            return Err(ContractError::AcceptanceDeadlinePassed {})
+        }
+        Some(_) => (),
+    }
+    vesting_info.acceptance_deadline = None;
+
+    save_vesting(deps.storage, &vesting_info)?;
+    Ok(Response::new()
+        .add_attribute("method", "try_accept_allocation")
+        .add_attribute("recipient", info.sender)
+        .add_attribute("vesting_id", vesting_info.vesting_id.to_string()))
+}
+
+/// ## Description
+/// Deactivates a vesting which has not been accepted before its deadline and forfeits it.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **recipient** is an object of type [`String`] which the address of a protocol's recipient address.
+///
+/// - **vesting_id** is an [`Option`] of type [`u64`] which is the vesting position of the recipient.
+pub fn try_reclaim_unaccepted(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: String,
+    vesting_id: Option<u64>,
+) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+
+    // Can only be called by a reviewer
+    assert_role(deps.storage, Role::Reviewer, &info.sender)?;
+
+    let validated_recipient = deps.api.addr_validate(&recipient)?;
+    assert_not_paused(deps.storage, &env, &config, &validated_recipient)?;
+    let mut vesting_info = load_vesting(deps.storage, &validated_recipient, vesting_id)?;
+
+    if !vesting_info.active {
+        return Err(ContractError::VestingNotActive {});
+    }
+    match vesting_info.acceptance_deadline {
+        None => return Err(ContractError::NotPendingAcceptance {}),
+        Some(deadline) if env.block.time.seconds() < deadline => {
+            return Err(ContractError::AcceptanceDeadlineNotReached {})
+        }
+        Some(_) => (),
+    }
+
+    // Nothing can have been claimed, so the whole allocation is forfeited
+    let forfeited_amount = vesting_info.vested_amount;
+    vesting_info.active = false;
+    vesting_info.vested_amount = Uint128::zero();
+
+    let forfeit_res = forfeit_vesting(
+        deps.storage,
+        &deps.querier,
+        &env,
+        &config,
+        &mut vesting_info,
+        forfeited_amount,
+    )?;
+    Ok(Response::new()
+        .add_submessages(forfeit_res.messages)
+        .add_attribute("method", "try_reclaim_unaccepted")
+        .add_attribute("recipient", recipient)
+        .add_attribute("vesting_id", vesting_info.vesting_id.to_string())
+        .add_attributes(forfeit_res.attributes))
+}
+
+/// ## Description
+/// Deactivates the sender's own vesting and forfeits its remainder.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **vesting_id** is an [`Option`] of type [`u64`] which is the vesting position of the sender.
+///
+/// - **keep_claimable** is an object of type [`bool`] which is whether to pay out the periods already earned.
+pub fn try_renounce(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    vesting_id: Option<u64>,
+    keep_claimable: bool,
+) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    assert_not_paused(deps.storage, &env, &config, &info.sender)?;
+    let vesting_info = load_vesting(deps.storage, &info.sender, vesting_id)?;
+    let mut vesting_info = reconcile_vesting(deps.storage, &deps.querier, &env, vesting_info)?;
+
+    if !vesting_info.active {
+        return Err(ContractError::VestingNotActive {});
+    }
+
+    let mut msgs: Vec<SubMsg> = vec![];
+    let mut claimed_amount = Uint128::zero();
+    let mut kept_amount = Uint128::zero();
+    if keep_claimable {
+        let (eligible_periods, earned_amount) =
+            compute_claimable(&vesting_info, &config.clock, clock_now(&config.clock, &env));
+        // Any staked part of the earned amount remains claimable once unbonded
+        let claimable_amount = std::cmp::min(
+            earned_amount,
+            vesting_info.payable_amount(env.block.time.seconds()),
+        );
+        let mut claimed_coins = vesting_info.claim(eligible_periods, claimable_amount);
+        if !claimable_amount.is_zero() {
+            claimed_coins.push(Coin::new(claimable_amount.u128(), config.denom.clone()));
+            msgs.push(payout_msg(
+                deps.storage,
+                &vesting_info.recipient,
+                merge_coins(claimed_coins),
+            )?);
+        }
+        claimed_amount = claimable_amount;
+        kept_amount = earned_amount - claimable_amount;
+    }
+
+    // Everything not earned is forfeited
+    let forfeited_amount = vesting_info.vested_amount - kept_amount;
+    vesting_info.active = false;
+    vesting_info.vested_amount = kept_amount;
+
+    let forfeit_res = forfeit_vesting(
+        deps.storage,
+        &deps.querier,
+        &env,
+        &config,
+        &mut vesting_info,
+        forfeited_amount,
+    )?;
+    Ok(Response::new()
+        .add_submessages(msgs)
+        .add_submessages(forfeit_res.messages)
+        .add_attribute("method", "try_renounce")
+        .add_attribute("recipient", info.sender)
+        .add_attribute("vesting_id", vesting_info.vesting_id.to_string())
+        .add_attribute("claimed_amount", claimed_amount)
+        .add_attributes(forfeit_res.attributes))
+}
+
+/// ## Description
+/// Sweeps the unclaimed amount of a vesting whose claim expiry has passed to the forfeit destination.
+/// Can be called by anyone.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **recipient** is an object of type [`String`] which the address of a protocol's recipient address.
+///
+/// - **vesting_id** is an [`Option`] of type [`u64`] which is the vesting position of the recipient.
+pub fn try_expire_vesting(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: String,
+    vesting_id: Option<u64>,
+) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let validated_recipient = deps.api.addr_validate(&recipient)?;
+    assert_not_paused(deps.storage, &env, &config, &validated_recipient)?;
+    let vesting_info = load_vesting(deps.storage, &validated_recipient, vesting_id)?;
+    let mut vesting_info = reconcile_vesting(deps.storage, &deps.querier, &env, vesting_info)?;
+
+    if vesting_info.expired || vesting_info.vested_amount.is_zero() {
+        return Err(ContractError::NothingToExpire {});
+    }
+    match claim_expiry_time(&config, &vesting_info) {
+        Some(expiry_time) if clock_now(&config.clock, &env) >= expiry_time => (),
+        _ => return Err(ContractError::ClaimExpiryNotReached {}),
+    }
+
+    let expired_amount = vesting_info.vested_amount;
+    vesting_info.active = false;
+    vesting_info.expired = true;
+    vesting_info.vested_amount = Uint128::zero();
+
+    let mut stats = STATS.load(deps.storage)?;
+    stats.total_expired += expired_amount;
+    STATS.save(deps.storage, &stats)?;
+
+    let forfeit_res = forfeit_vesting(
+        deps.storage,
+        &deps.querier,
+        &env,
+        &config,
+        &mut vesting_info,
+        expired_amount,
+    )?;
+    Ok(Response::new()
+        .add_submessages(forfeit_res.messages)
+        .add_attribute("method", "try_expire_vesting")
+        .add_attribute("sender", info.sender)
+        .add_attribute("recipient", recipient)
+        .add_attribute("vesting_id", vesting_info.vesting_id.to_string())
+        .add_attribute("expired_amount", expired_amount)
+        .add_attributes(forfeit_res.attributes))
+}
+
+/// ## Description
+/// Returns `coins` summed by denom and sorted by denom without zero amounts, as expected by the
+/// bank module.
+///
+/// ## Params
+/// - **coins** is a vector of [`Coin`] which are the coins to be merged.
+fn merge_coins(coins: Vec<Coin>) -> Vec<Coin> {
+    let mut amounts: BTreeMap<String, Uint128> = BTreeMap::new();
+    for coin in coins {
+        *amounts.entry(coin.denom).or_default() += coin.amount;
+    }
+    amounts
+        .into_iter()
+        .filter(|(_, amount)| !amount.is_zero())
+        .map(|(denom, amount)| Coin::new(amount.u128(), denom))
+        .collect()
+}
+
+/// ## Description
+/// Stores a vesting after `amount` has been forfeited from it and handles the forfeited amount along
+/// with the released basket coins. Everything staked from a vesting which is no longer active is
+/// undelegated first, and the forfeited amount which is not held by the contract is left pending
+/// until settled by [`try_settle_forfeit`].
+/// Returns a [`Response`] holding the forfeiture messages and attributes to be merged by the caller.
+///
+/// ## Params
+/// - **storage** is an object of type [`Storage`].
+///
+/// - **querier** is an object of type [`QuerierWrapper`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **config** is an object of type [`Config`].
+///
+/// - **vesting_info** is an object of type [`VestingInfo`] which is the vesting to be stored.
+///
+/// - **amount** is an object of type [`Uint128`] which is the forfeited amount.
+fn forfeit_vesting(
+    storage: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    env: &Env,
+    config: &Config,
+    vesting_info: &mut VestingInfo,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let now = env.block.time.seconds();
+    let forfeited_basket = vesting_info.forfeit_basket();
+
+    let mut msgs: Vec<SubMsg> = vec![];
+    if !vesting_info.active {
+        // Undelegating withdraws the rewards, which are not forfeited
+        let validators: Vec<String> = vesting_info
+            .delegations
+            .iter()
+            .map(|delegation| delegation.validator.clone())
+            .collect();
+        credit_rewards(storage, querier, env, &validators)?;
+        vesting_info.release_unbondings(now);
+        for delegation in std::mem::take(&mut vesting_info.delegations) {
+            msgs.push(SubMsg::new(CosmosMsg::Staking(StakingMsg::Undelegate {
+                validator: delegation.validator,
+                amount: Coin::new(delegation.amount.u128(), config.denom.clone()),
+            })));
+            vesting_info.unbondings.push(Unbonding {
+                amount: delegation.amount,
+                release_time: now + config.unbonding_period,
+            });
+        }
+    }
+
+    // The forfeited amount is paid before the recipient out of the amount held by the contract
+    vesting_info.forfeit_pending += amount;
+    let settled_amount = std::cmp::min(
+        vesting_info.forfeit_pending,
+        vesting_info.liquid_amount(now),
+    );
+    vesting_info.forfeit_pending -= settled_amount;
+    save_vesting(storage, vesting_info)?;
+
+    let forfeit_res = forfeit(storage, config, settled_amount, forfeited_basket)?;
+    let mut res = Response::new()
+        .add_submessages(msgs)
+        .add_submessages(forfeit_res.messages)
+        .add_attributes(forfeit_res.attributes);
+    if !vesting_info.forfeit_pending.is_zero() {
+        res = res.add_attribute("forfeit_pending", vesting_info.forfeit_pending);
+    }
+    Ok(res)
+}
+
+/// ## Description
+/// Handles the unvested remainder of a forfeited vesting according to the configured [`ForfeitMode`].
+/// Redistributed amounts are credited to the eligible vestings when they are next loaded.
+/// Returns a [`Response`] holding the forfeiture messages and attributes to be merged by the caller.
+///
+/// ## Params
+/// - **storage** is an object of type [`Storage`].
+///
+/// - **config** is an object of type [`Config`].
+///
+/// - **amount** is an object of type [`Uint128`] which is the forfeited amount.
+///
+/// - **basket** is a vector of [`Coin`] which are the forfeited basket coins.
+fn forfeit(
+    storage: &mut dyn Storage,
+    config: &Config,
+    amount: Uint128,
+    basket: Vec<Coin>,
+) -> Result<Response, ContractError> {
+    let basket = merge_coins(basket);
+    let mut res = Response::new().add_attribute("forfeited_amount", amount);
+    if !basket.is_empty() {
+        let basket_str: Vec<String> = basket.iter().map(|coin| coin.to_string()).collect();
+        res = res.add_attribute("forfeited_basket", basket_str.join(","));
+    }
+    if amount.is_zero() && basket.is_empty() {
+        return Ok(res);
+    }
+
+    let mut stats = STATS.load(storage)?;
+    stats.total_forfeited += amount;
+
+    let mut forfeit_mode = config.forfeit_mode;
+    if forfeit_mode == ForfeitMode::Redistribute && !amount.is_zero() {
+        let mut redistribution = REDISTRIBUTION.may_load(storage)?.unwrap_or_default();
+        if redistribution.total_weight.is_zero() {
+            // Fall back to transferring if there is no one left to redistribute to
+            forfeit_mode = ForfeitMode::Transfer;
+        } else {
+            // Credited to the eligible vestings pro-rata to their total amounts as they are
+            // loaded, rather than to each of them right away. The forfeited vesting must already
+            // be stored as inactive, so that its weight no longer takes a share
+            redistribution.index += Decimal::from_ratio(amount, redistribution.total_weight);
+            REDISTRIBUTION.save(storage, &redistribution)?;
+        }
+    }
+
+    // Basket coins are not redistributed, but sent to `forfeit_address` unless burned
+    let mut forfeited_coins = basket;
+    if forfeit_mode != ForfeitMode::Redistribute {
+        forfeited_coins.push(Coin::new(amount.u128(), config.denom.clone()));
+    }
+    let forfeited_coins = merge_coins(forfeited_coins);
+    match forfeit_mode {
+        ForfeitMode::Transfer | ForfeitMode::Redistribute => {
+            if !forfeited_coins.is_empty() {
+                res = res.add_submessage(SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                    to_address: config.forfeit_address.to_string(),
+                    amount: forfeited_coins,
+                })));
+            }
+        }
+        ForfeitMode::Burn => {
+            stats.total_burned += amount;
+            res = res
+                .add_submessage(SubMsg::new(CosmosMsg::Bank(BankMsg::Burn {
+                    amount: forfeited_coins,
+                })))
+                .add_attribute("burned_amount", amount);
+        }
+    }
+
+    STATS.save(storage, &stats)?;
+    Ok(res.add_attribute("forfeit_mode", format!("{:?}", forfeit_mode)))
+}
+
+/// ## Description
+/// Updates the contract settings.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **forfeit_address** is an object of type [`Option<String>`] which is the new address receiving forfeited vestings.
+///
+/// - **forfeit_mode** is an object of type [`Option<ForfeitMode>`] which is the new forfeiture mode.
+pub fn try_update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    forfeit_address: Option<String>,
+    forfeit_mode: Option<ForfeitMode>,
+) -> Result<Response, ContractError> {
+    // Can only be called by an admin
+    assert_role(deps.storage, Role::Admin, &info.sender)?;
+
+    let mut config: Config = CONFIG.load(deps.storage)?;
+    if let Some(forfeit_address) = forfeit_address {
+        config.forfeit_address = deps.api.addr_validate(&forfeit_address)?;
+    }
+    if let Some(forfeit_mode) = forfeit_mode {
+        config.forfeit_mode = forfeit_mode;
+    }
+
+    CONFIG.save(deps.storage, &config)?;
+    Ok(Response::new()
+        .add_attribute("method", "try_update_config")
+        .add_attribute("forfeit_address", config.forfeit_address)
+        .add_attribute("forfeit_mode", format!("{:?}", config.forfeit_mode)))
+}
+
+/// ## Description
+/// Grants a role to an address.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **role** is an object of type [`Role`] which is the role to be granted.
+///
+/// - **address** is an object of type [`String`] which is the address to be granted with the role.
+pub fn try_grant_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    // Can only be called by an admin
+    assert_role(deps.storage, Role::Admin, &info.sender)?;
+
+    let validated_address = deps.api.addr_validate(&address)?;
+    let mut roles = ROLES.load(deps.storage)?;
+    if roles.has(role, &validated_address) {
+        return Err(ContractError::RoleAlreadyGranted { address });
+    }
+    roles.members_mut(role).push(validated_address);
+
+    ROLES.save(deps.storage, &roles)?;
+    Ok(Response::new()
+        .add_attribute("method", "try_grant_role")
+        .add_attribute("role", format!("{:?}", role))
+        .add_attribute("address", address))
+}
+
+/// ## Description
+/// Revokes a role from an address.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **role** is an object of type [`Role`] which is the role to be revoked.
+///
+/// - **address** is an object of type [`String`] which is the address to be revoked from the role.
+pub fn try_revoke_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response, ContractError> {
+    // Can only be called by an admin
+    assert_role(deps.storage, Role::Admin, &info.sender)?;
+
+    let validated_address = deps.api.addr_validate(&address)?;
+    let mut roles = ROLES.load(deps.storage)?;
+    if !roles.has(role, &validated_address) {
+        return Err(ContractError::RoleNotGranted { address });
+    }
+    // The contract must always have at least one admin
+    if role == Role::Admin && roles.admins.len() == 1 {
+        return Err(ContractError::LastAdmin {});
+    }
+    roles
+        .members_mut(role)
+        .retain(|addr| *addr != validated_address);
+
+    ROLES.save(deps.storage, &roles)?;
+    Ok(Response::new()
+        .add_attribute("method", "try_revoke_role")
+        .add_attribute("role", format!("{:?}", role))
+        .add_attribute("address", address))
+}
+
+/// ## Description
+/// Halts claims and tollgate changes, either for the whole contract or a single recipient.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **recipient** is an object of type [`Option<String>`] which is the recipient to be frozen.
+///
+/// - **auto_unpause_time** is an object of type [`Option<u64>`] which is the time when the pause is lifted, which must be in the future.
+pub fn try_pause(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: Option<String>,
+    auto_unpause_time: Option<u64>,
+) -> Result<Response, ContractError> {
+    // Can only be called by a guardian
+    assert_role(deps.storage, Role::Guardian, &info.sender)?;
+
+    // A pause lifted at once would have no effect
+    if auto_unpause_time.map_or(false, |time| time <= env.block.time.seconds()) {
+        return Err(ContractError::InvalidAutoUnpauseTime {});
+    }
+
+    let pause = PauseInfo { auto_unpause_time };
+    match &recipient {
+        Some(recipient) => {
+            let validated_recipient = deps.api.addr_validate(recipient)?;
+            FROZEN_RECIPIENTS.save(deps.storage, &validated_recipient, &pause)?;
+        }
+        None => {
+            let mut config: Config = CONFIG.load(deps.storage)?;
+            config.pause = Some(pause);
+            CONFIG.save(deps.storage, &config)?;
+        }
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "try_pause")
+        .add_attribute("recipient", recipient.unwrap_or_default())
+        .add_attribute(
+            "auto_unpause_time",
+            auto_unpause_time.map_or_else(String::new, |time| time.to_string()),
+        ))
+}
+
+/// ## Description
+/// Lifts a pause, either for the whole contract or a single recipient.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **recipient** is an object of type [`Option<String>`] which is the recipient to be unfrozen.
+pub fn try_unpause(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    // Can only be called by a guardian
+    assert_role(deps.storage, Role::Guardian, &info.sender)?;
+
+    let now = env.block.time.seconds();
+    match &recipient {
+        Some(recipient) => {
+            let validated_recipient = deps.api.addr_validate(recipient)?;
+            match FROZEN_RECIPIENTS.may_load(deps.storage, &validated_recipient)? {
+                Some(pause) if pause.is_active(now) => (),
+                _ => return Err(ContractError::NotPaused {}),
+            }
+            FROZEN_RECIPIENTS.remove(deps.storage, &validated_recipient);
+        }
+        None => {
+            let mut config: Config = CONFIG.load(deps.storage)?;
+            match config.pause {
+                Some(pause) if pause.is_active(now) => (),
+                _ => return Err(ContractError::NotPaused {}),
+            }
+            config.pause = None;
+            CONFIG.save(deps.storage, &config)?;
+        }
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "try_unpause")
+        .add_attribute("recipient", recipient.unwrap_or_default()))
+}
+
+/// ## Description
+/// Sends the balance of `denom` held by the contract in excess of the outstanding vestings to `to`.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **denom** is an object of type [`String`] which is the denom to be swept.
+///
+/// - **to** is an object of type [`String`] which is the address receiving the excess.
+pub fn try_sweep_excess(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+    to: String,
+) -> Result<Response, ContractError> {
+    // Can only be called by an admin
+    assert_role(deps.storage, Role::Admin, &info.sender)?;
+
+    let config: Config = CONFIG.load(deps.storage)?;
+    let validated_to = deps.api.addr_validate(&to)?;
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, denom.clone())?
+        .amount;
+    let outstanding_amount = outstanding_amount(deps.storage, &env, &config, &denom)?;
+    let excess_amount = balance.saturating_sub(outstanding_amount);
+    if excess_amount.is_zero() {
+        return Err(ContractError::NoExcess {});
+    }
+
+    Ok(Response::new()
+        .add_submessage(SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: validated_to.to_string(),
+            amount: coins(excess_amount.into(), denom.clone()),
+        })))
+        .add_attribute("method", "try_sweep_excess")
+        .add_attribute("denom", denom)
+        .add_attribute("to", validated_to)
+        .add_attribute("excess_amount", excess_amount))
+}
+
+/// ## Description
+/// Returns the amount of `denom` the contract must hold for the vestings, the accrued and credited
+/// staking rewards, the failed payouts, and the unresolved IBC payouts, which may be refunded.
+///
+/// ## Params
+/// - **storage** is an object of type [`Storage`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **config** is an object of type [`Config`].
+///
+/// - **denom** is an object of type [`str`] which is the denom to be computed.
+fn outstanding_amount(
+    storage: &dyn Storage,
+    env: &Env,
+    config: &Config,
+    denom: &str,
+) -> StdResult<Uint128> {
+    // Funds backing the vestings, including their baskets and the redistributed forfeitures not
+    // yet credited to them, are never swept
+    let now = env.block.time.seconds();
+    let index = REDISTRIBUTION.may_load(storage)?.unwrap_or_default().index;
+    let outstanding_amount = VESTING_INFO
+        .range(storage, None, None, Order::Ascending)
+        .try_fold(Uint128::zero(), |sum, item| {
+            item.map(|(_, vesting)| {
+                let basket_amount = vesting
+                    .basket
+                    .iter()
+                    .filter(|basket_coin| basket_coin.denom == denom)
+                    .fold(Uint128::zero(), |sum, basket_coin| {
+                        sum + basket_coin.vested_amount
+                    });
+                // Staked amounts are not part of the balance
+                if denom == config.denom {
+                    sum + vesting.liquid_amount(now)
+                        + vesting.redistribution_share(index)
+                        + basket_amount
+                } else {
+                    sum + basket_amount
+                }
+            })
+        })?;
+    // Nor are the staking rewards accrued by the delegations and not credited yet
+    let mut outstanding_amount = outstanding_amount;
+    for item in VALIDATOR_DELEGATIONS.range(storage, None, None, Order::Ascending) {
+        let ((validator, _), delegator_info) = item?;
+        let validator_info = VALIDATORS.load(storage, &validator)?;
+        outstanding_amount = delegator_info
+            .pending_rewards(&validator_info)
+            .iter()
+            .filter(|coin| coin.denom == denom)
+            .fold(outstanding_amount, |sum, coin| sum + coin.amount);
+    }
+    // Nor are the staking rewards and failed payouts credited to recipients
+    for credits in [REWARDS, CREDITS] {
+        outstanding_amount = credits
+            .range(storage, None, None, Order::Ascending)
+            .try_fold(outstanding_amount, |sum, item| {
+                item.map(|(_, coins)| {
+                    coins
+                        .iter()
+                        .filter(|coin| coin.denom == denom)
+                        .fold(sum, |sum, coin| sum + coin.amount)
+                })
+            })?;
+    }
+    // Nor are the IBC payouts which may be refunded
+    Ok(outstanding_amount
+        + IBC_PAYOUT_TOTALS
+            .may_load(storage, denom)?
+            .unwrap_or_default())
+}
+
+/// ## Description
+/// Adds new vestings funded by the sent tokens.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **vestings** is a vector of [`Vesting`] which are the vestings to be added.
+pub fn try_add_vestings(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    vestings: Vec<Vesting>,
+) -> Result<Response, ContractError> {
+    // Can only be called by a funder
+    assert_role(deps.storage, Role::Funder, &info.sender)?;
+
+    let num_vestings = vestings.len();
+    store_vestings(deps, &env, &info.funds, vestings)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "try_add_vestings")
+        .add_attribute("num_vestings", num_vestings.to_string()))
+}
+
+/// ## Description
+/// Increases the total amount of an active vesting by the sent tokens.
+/// The top up is spread evenly across all periods of the vesting.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **recipient** is an object of type [`String`] which the address of a protocol's recipient address.
+///
+/// - **vesting_id** is an [`Option`] of type [`u64`] which is the vesting position of the recipient.
+pub fn try_top_up_vesting(
+    deps: DepsMut,
+    info: MessageInfo,
+    recipient: String,
+    vesting_id: Option<u64>,
+) -> Result<Response, ContractError> {
+    // Can only be called by a funder
+    assert_role(deps.storage, Role::Funder, &info.sender)?;
+
+    // Check sent vesting asset denom
+    if info.funds.len() != 1 || info.funds[0].denom != DENOM {
+        return Err(ContractError::MismatchedAssetType {});
+    }
+    let top_up_amount = info.funds[0].amount;
+
+    let validated_recipient = deps.api.addr_validate(&recipient)?;
+    let mut vesting_info = load_vesting(deps.storage, &validated_recipient, vesting_id)?;
+    if !vesting_info.active {
+        return Err(ContractError::VestingNotActive {});
+    }
+
+    vesting_info.top_up(top_up_amount);
+
+    save_vesting(deps.storage, &vesting_info)?;
+    Ok(Response::new()
+        .add_attribute("method", "try_top_up_vesting")
+        .add_attribute("recipient", recipient)
+        .add_attribute("vesting_id", vesting_info.vesting_id.to_string())
+        .add_attribute("top_up_amount", top_up_amount)
+        .add_attribute("total_amount", vesting_info.total_amount))
+}
+
+/// ## Description
+/// Reconciles the total delegations of the vestings to `validators` with the contract's actual
+/// delegations, which are lowered by slashing. The shortfall of each validator is booked as a loss
+/// of the vestings delegating to it, pro-rata to their delegated amounts, so that it is never paid
+/// out of the funds of other vestings.
+///
+/// ## Params
+/// - **storage** is an object of type [`Storage`].
+///
+/// - **querier** is an object of type [`QuerierWrapper`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **validators** is a slice of [`String`] which are the validators to be reconciled.
+fn reconcile_delegations(
+    storage: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    env: &Env,
+    validators: &[String],
+) -> StdResult<()> {
+    let mut validators = validators.to_vec();
+    validators.sort_unstable();
+    validators.dedup();
+    for validator in validators {
+        let actual_amount = querier
+            .query_delegation(&env.contract.address, &validator)?
+            .map(|delegation| delegation.amount.amount)
+            .unwrap_or_default();
+
+        let recorded_amount = VALIDATORS
+            .may_load(storage, &validator)?
+            .unwrap_or_default()
+            .total_amount;
+        if actual_amount >= recorded_amount {
+            continue;
+        }
+
+        // Only the vestings delegating to the slashed validator are loaded
+        let delegators = VALIDATOR_DELEGATIONS
+            .prefix(&validator)
+            .range(storage, None, None, Order::Ascending)
+            .map(|item| {
+                item.map(|(vesting_id, delegator_info)| (vesting_id, delegator_info.amount))
+            })
+            .collect::<StdResult<Vec<(u64, Uint128)>>>()?;
+        let amounts: Vec<Uint128> = delegators.iter().map(|(_, amount)| *amount).collect();
+        let losses = split_amount(recorded_amount - actual_amount, &amounts);
+        for ((vesting_id, _), loss) in delegators.into_iter().zip(losses) {
+            let recipient = VESTING_RECIPIENT.load(storage, vesting_id)?;
+            let mut vesting_info = VESTING_INFO.load(storage, (&recipient, vesting_id))?;
+            settle_redistribution(storage, &mut vesting_info)?;
+            vesting_info.slash(&validator, loss);
+            save_vesting(storage, &vesting_info)?;
+        }
+    }
+    Ok(())
+}
+
+/// ## Description
+/// Reconciles the delegations of a vesting with the contract's actual delegations, as in
+/// [`reconcile_delegations`], and returns the vesting as reloaded afterwards.
+///
+/// ## Params
+/// - **storage** is an object of type [`Storage`].
+///
+/// - **querier** is an object of type [`QuerierWrapper`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **vesting_info** is an object of type [`VestingInfo`] which is the vesting to be reconciled.
+fn reconcile_vesting(
+    storage: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    env: &Env,
+    vesting_info: VestingInfo,
+) -> StdResult<VestingInfo> {
+    let validators: Vec<String> = vesting_info
+        .delegations
+        .iter()
+        .map(|delegation| delegation.validator.clone())
+        .collect();
+    if validators.is_empty() {
+        return Ok(vesting_info);
+    }
+    reconcile_delegations(storage, querier, env, &validators)?;
+    let mut vesting_info =
+        VESTING_INFO.load(storage, (&vesting_info.recipient, vesting_info.vesting_id))?;
+    settle_redistribution(storage, &mut vesting_info)?;
+    Ok(vesting_info)
+}
+
+/// ## Description
+/// Accrues the staking rewards accumulated by the contract's delegations to `validators` to the
+/// vestings delegating to them, pro-rata to their delegated amounts, by raising the reward indexes
+/// of the validators. The rewards are credited to the recipients as the delegations change. Must
+/// be called before the rewards are withdrawn, which happens on any change of a delegation.
+///
+/// ## Params
+/// - **storage** is an object of type [`Storage`].
+///
+/// - **querier** is an object of type [`QuerierWrapper`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **validators** is a slice of [`String`] which are the validators whose rewards are withdrawn.
+fn credit_rewards(
+    storage: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    env: &Env,
+    validators: &[String],
+) -> StdResult<()> {
+    let mut validators = validators.to_vec();
+    validators.sort_unstable();
+    validators.dedup();
+    for validator in validators {
+        let rewards = querier
+            .query_delegation(&env.contract.address, &validator)?
+            .map(|delegation| merge_coins(delegation.accumulated_rewards))
+            .unwrap_or_default();
+        let mut validator_info = VALIDATORS
+            .may_load(storage, &validator)?
+            .unwrap_or_default();
+        if rewards.is_empty() || validator_info.total_amount.is_zero() {
+            continue;
+        }
+
+        // The rounding remainder of each share is left in the contract
+        for coin in rewards {
+            let index = Decimal::from_ratio(coin.amount, validator_info.total_amount);
+            match validator_info
+                .reward_indexes
+                .iter_mut()
+                .find(|reward_index| reward_index.denom == coin.denom)
+            {
+                Some(reward_index) => reward_index.index += index,
+                None => validator_info.reward_indexes.push(RewardIndex {
+                    denom: coin.denom,
+                    index,
+                }),
+            }
+        }
+        VALIDATORS.save(storage, &validator, &validator_info)?;
+    }
+    Ok(())
+}
+
+/// ## Description
+/// Loads the sender's own vesting to be staked, which must be active, accepted and not paused.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **config** is an object of type [`Config`].
+///
+/// - **sender** is an object of type [`Addr`] which is the recipient of the vesting.
+///
+/// - **vesting_id** is an [`Option`] of type [`u64`] which is the vesting position of the sender.
+///
+/// - **amount** is an object of type [`Uint128`] which is the amount to be staked.
+fn load_stakeable_vesting(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    sender: &Addr,
+    vesting_id: Option<u64>,
+    amount: Uint128,
+) -> Result<VestingInfo, ContractError> {
+    assert_not_paused(deps.storage, env, config, sender)?;
+    if amount.is_zero() {
+        return Err(ContractError::ZeroStakingAmount {});
+    }
+    let vesting_info = load_vesting(deps.storage, sender, vesting_id)?;
+    if !vesting_info.active {
+        return Err(ContractError::VestingNotActive {});
+    }
+    if vesting_info.acceptance_deadline.is_some() {
+        return Err(ContractError::PendingAcceptance {});
+    }
+    Ok(vesting_info)
+}
+
+/// ## Description
+/// Returns a [`ContractError::ValidatorNotFound`] if `validator` is not a validator of the chain.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **validator** is an object of type [`String`] which is the operator address of the validator.
+fn assert_validator(deps: Deps, validator: &str) -> Result<(), ContractError> {
+    if deps.querier.query_validator(validator)?.is_none() {
+        return Err(ContractError::ValidatorNotFound {
+            validator: validator.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// ## Description
+/// Decreases the delegation of a vesting to `validator` by `amount`, removing it once empty.
+/// Returns a [`ContractError::InsufficientDelegation`] if less than `amount` is delegated.
+///
+/// ## Params
+/// - **vesting_info** is an object of type [`VestingInfo`] which is the vesting delegating.
+///
+/// - **validator** is an object of type [`String`] which is the operator address of the validator.
+///
+/// - **amount** is an object of type [`Uint128`] which is the amount to be removed.
+fn remove_delegation(
+    vesting_info: &mut VestingInfo,
+    validator: &str,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    let delegation = vesting_info
+        .delegations
+        .iter_mut()
+        .find(|delegation| delegation.validator == validator)
+        .filter(|delegation| delegation.amount >= amount)
+        .ok_or_else(|| ContractError::InsufficientDelegation {
+            validator: validator.to_string(),
+        })?;
+    delegation.amount -= amount;
+    vesting_info
+        .delegations
+        .retain(|delegation| !delegation.amount.is_zero());
+    Ok(())
+}
+
+/// ## Description
+/// Increases the delegation of a vesting to `validator` by `amount`.
+///
+/// ## Params
+/// - **vesting_info** is an object of type [`VestingInfo`] which is the vesting delegating.
+///
+/// - **validator** is an object of type [`String`] which is the operator address of the validator.
+///
+/// - **amount** is an object of type [`Uint128`] which is the amount to be added.
+fn add_delegation(vesting_info: &mut VestingInfo, validator: String, amount: Uint128) {
+    match vesting_info
+        .delegations
+        .iter_mut()
+        .find(|delegation| delegation.validator == validator)
+    {
+        Some(delegation) => delegation.amount += amount,
+        None => vesting_info
+            .delegations
+            .push(Delegation { validator, amount }),
+    }
+}
+
+/// ## Description
+/// Delegates the unclaimed amount of the sender's own vesting, held by the contract, to a validator.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **vesting_id** is an [`Option`] of type [`u64`] which is the vesting position of the sender.
+///
+/// - **validator** is an object of type [`String`] which is the operator address of the validator.
+///
+/// - **amount** is an object of type [`Uint128`] which is the amount to be delegated.
+pub fn try_delegate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    vesting_id: Option<u64>,
+    validator: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let vesting_info = load_stakeable_vesting(
+        deps.as_ref(),
+        &env,
+        &config,
+        &info.sender,
+        vesting_id,
+        amount,
+    )?;
+    let mut vesting_info = reconcile_vesting(deps.storage, &deps.querier, &env, vesting_info)?;
+    assert_validator(deps.as_ref(), &validator)?;
+    // Delegating withdraws the rewards of an existing delegation
+    credit_rewards(
+        deps.storage,
+        &deps.querier,
+        &env,
+        std::slice::from_ref(&validator),
+    )?;
+
+    let now = env.block.time.seconds();
+    if amount > vesting_info.payable_amount(now) {
+        return Err(ContractError::InsufficientLiquidAmount {});
+    }
+    vesting_info.release_unbondings(now);
+    add_delegation(&mut vesting_info, validator.clone(), amount);
+
+    save_vesting(deps.storage, &vesting_info)?;
+    Ok(Response::new()
+        .add_submessage(SubMsg::new(CosmosMsg::Staking(StakingMsg::Delegate {
+            validator: validator.clone(),
+            amount: Coin::new(amount.u128(), config.denom),
+        })))
+        .add_attribute("method", "try_delegate")
+        .add_attribute("recipient", info.sender)
+        .add_attribute("vesting_id", vesting_info.vesting_id.to_string())
+        .add_attribute("validator", validator)
+        .add_attribute("amount", amount))
+}
+
+/// ## Description
+/// Moves a delegation of the sender's own vesting to another validator.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **vesting_id** is an [`Option`] of type [`u64`] which is the vesting position of the sender.
+///
+/// - **src_validator** is an object of type [`String`] which is the validator currently delegated to.
+///
+/// - **dst_validator** is an object of type [`String`] which is the validator to be delegated to.
+///
+/// - **amount** is an object of type [`Uint128`] which is the amount to be redelegated.
+pub fn try_redelegate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    vesting_id: Option<u64>,
+    src_validator: String,
+    dst_validator: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let vesting_info = load_stakeable_vesting(
+        deps.as_ref(),
+        &env,
+        &config,
+        &info.sender,
+        vesting_id,
+        amount,
+    )?;
+    let mut vesting_info = reconcile_vesting(deps.storage, &deps.querier, &env, vesting_info)?;
+    assert_validator(deps.as_ref(), &dst_validator)?;
+    // Redelegating withdraws the rewards of both delegations
+    credit_rewards(
+        deps.storage,
+        &deps.querier,
+        &env,
+        &[src_validator.clone(), dst_validator.clone()],
+    )?;
+
+    remove_delegation(&mut vesting_info, &src_validator, amount)?;
+    add_delegation(&mut vesting_info, dst_validator.clone(), amount);
+
+    save_vesting(deps.storage, &vesting_info)?;
+    Ok(Response::new()
+        .add_submessage(SubMsg::new(CosmosMsg::Staking(StakingMsg::Redelegate {
+            src_validator: src_validator.clone(),
+            dst_validator: dst_validator.clone(),
+            amount: Coin::new(amount.u128(), config.denom),
+        })))
+        .add_attribute("method", "try_redelegate")
+        .add_attribute("recipient", info.sender)
+        .add_attribute("vesting_id", vesting_info.vesting_id.to_string())
+        .add_attribute("src_validator", src_validator)
+        .add_attribute("dst_validator", dst_validator)
+        .add_attribute("amount", amount))
+}
+
+/// ## Description
+/// Undelegates a delegation of the sender's own vesting. The amount can be claimed once it is
+/// returned to the contract at the end of the unbonding period.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **vesting_id** is an [`Option`] of type [`u64`] which is the vesting position of the sender.
+///
+/// - **validator** is an object of type [`String`] which is the operator address of the validator.
+///
+/// - **amount** is an object of type [`Uint128`] which is the amount to be undelegated.
+pub fn try_undelegate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    vesting_id: Option<u64>,
+    validator: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let vesting_info = load_stakeable_vesting(
+        deps.as_ref(),
+        &env,
+        &config,
+        &info.sender,
+        vesting_id,
+        amount,
+    )?;
+    let mut vesting_info = reconcile_vesting(deps.storage, &deps.querier, &env, vesting_info)?;
+
+    // Undelegating withdraws the rewards of the delegation
+    credit_rewards(
+        deps.storage,
+        &deps.querier,
+        &env,
+        std::slice::from_ref(&validator),
+    )?;
+
+    let now = env.block.time.seconds();
+    remove_delegation(&mut vesting_info, &validator, amount)?;
+    vesting_info.release_unbondings(now);
+    let release_time = now + config.unbonding_period;
+    vesting_info.unbondings.push(Unbonding {
+        amount,
+        release_time,
+    });
+
+    save_vesting(deps.storage, &vesting_info)?;
+    Ok(Response::new()
+        .add_submessage(SubMsg::new(CosmosMsg::Staking(StakingMsg::Undelegate {
+            validator: validator.clone(),
+            amount: Coin::new(amount.u128(), config.denom),
+        })))
+        .add_attribute("method", "try_undelegate")
+        .add_attribute("recipient", info.sender)
+        .add_attribute("vesting_id", vesting_info.vesting_id.to_string())
+        .add_attribute("validator", validator)
+        .add_attribute("amount", amount)
+        .add_attribute("release_time", release_time.to_string()))
+}
+
+/// ## Description
+/// Withdraws the staking rewards of the delegations of the sender's vestings, crediting them to all
+/// recipients delegating to the same validators, and sends the rewards credited to the sender.
+/// Rewards are not part of the vestings, so they are never forfeited.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+pub fn try_withdraw_rewards(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    assert_not_paused(deps.storage, &env, &config, &info.sender)?;
+
+    let mut validators = VESTING_INFO
+        .prefix(&info.sender)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            item.map(|(_, vesting_info)| {
+                vesting_info
+                    .delegations
+                    .into_iter()
+                    .map(|delegation| delegation.validator)
+                    .collect::<Vec<String>>()
+            })
+        })
+        .collect::<StdResult<Vec<Vec<String>>>>()?
+        .concat();
+    validators.sort_unstable();
+    validators.dedup();
+    credit_rewards(deps.storage, &deps.querier, &env, &validators)?;
+    settle_rewards(deps.storage, &info.sender)?;
+
+    let rewards = REWARDS
+        .may_load(deps.storage, &info.sender)?
+        .unwrap_or_default();
+    if rewards.is_empty() {
+        return Err(ContractError::NoRewards {});
+    }
+    REWARDS.remove(deps.storage, &info.sender);
+
+    // The rewards are sent once withdrawn to the contract
+    let mut msgs: Vec<SubMsg> = validators
+        .into_iter()
+        .map(|validator| {
+            SubMsg::new(CosmosMsg::Distribution(
+                DistributionMsg::WithdrawDelegatorReward { validator },
+            ))
+        })
+        .collect();
+    let rewards_str: Vec<String> = rewards.iter().map(|coin| coin.to_string()).collect();
+    msgs.push(payout_msg(deps.storage, &info.sender, rewards)?);
+
+    Ok(Response::new()
+        .add_submessages(msgs)
+        .add_attribute("method", "try_withdraw_rewards")
+        .add_attribute("recipient", info.sender)
+        .add_attribute("rewards", rewards_str.join(",")))
+}
+
+/// ## Description
+/// Records the vote of the sender on a governance proposal and has the contract vote with the
+/// tally of the recipients' votes. The contract casts a single weighted vote, in which every
+/// recipient who voted counts with the amount its vestings delegated when it last voted. The
+/// delegations of the recipients who have not voted are left out of the contract's vote.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **proposal_id** is an object of type [`u64`] which is the id of the governance proposal.
+///
+/// - **vote** is an object of type [`VoteOption`] which is the vote option.
+pub fn try_vote(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+    vote: VoteOption,
+) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    assert_not_paused(deps.storage, &env, &config, &info.sender)?;
+
+    let weight = delegated_weight(deps.storage, &info.sender)?;
+    if weight.is_zero() {
+        return Err(ContractError::NoDelegation {});
+    }
+
+    // Replace the sender's previous vote in the running tally of the proposal
+    let mut tally = VOTE_TALLIES
+        .may_load(deps.storage, proposal_id)?
+        .unwrap_or_default();
+    if let Some(previous) = VOTES.may_load(deps.storage, (proposal_id, &info.sender))? {
+        if let Some((_, sum)) = tally
+            .iter_mut()
+            .find(|(option, _)| *option == previous.vote)
+        {
+            *sum -= previous.weight;
+        }
+    }
+    add_vote_weight(&mut tally, vote.clone(), weight);
+    tally.retain(|(_, weight)| !weight.is_zero());
+    VOTE_TALLIES.save(deps.storage, proposal_id, &tally)?;
+    VOTES.save(
+        deps.storage,
+        (proposal_id, &info.sender),
+        &VoteRecord {
+            voter: info.sender.clone(),
+            vote: vote.clone(),
+            weight,
+        },
+    )?;
+
+    let total_weight = tally
+        .iter()
+        .fold(Uint128::zero(), |sum, (_, weight)| sum + *weight);
+    tally.sort_by_key(|(vote, _)| vote_option_number(vote));
+
+    // Weights must sum to exactly one, so the rounding remainder goes to the last option
+    let mut remaining_weight = Decimal::one();
+    let num_options = tally.len();
+    let options: Vec<(VoteOption, Decimal)> = tally
+        .into_iter()
+        .enumerate()
+        .map(|(i, (vote, weight))| {
+            let share = if i + 1 == num_options {
+                remaining_weight
+            } else {
+                Decimal::from_ratio(weight, total_weight)
+            };
+            remaining_weight -= share;
+            (vote, share)
+        })
+        .collect();
+    let gov_msg = CosmosMsg::Stargate {
+        type_url: "/cosmos.gov.v1beta1.MsgVoteWeighted".to_string(),
+        value: encode_vote_weighted(proposal_id, env.contract.address.as_str(), &options),
+    };
+
+    Ok(Response::new()
+        .add_submessage(SubMsg::new(gov_msg))
+        .add_attribute("method", "try_vote")
+        .add_attribute("recipient", info.sender)
+        .add_attribute("proposal_id", proposal_id.to_string())
+        .add_attribute("vote", format!("{:?}", vote))
+        .add_attribute("weight", weight))
+}
+
+/// ## Description
+/// Returns the amount currently delegated by the vestings of `recipient`.
+///
+/// ## Params
+/// - **storage** is an object of type [`Storage`].
+///
+/// - **recipient** is an object of type [`Addr`] which is the recipient of the vestings.
+fn delegated_weight(storage: &dyn Storage, recipient: &Addr) -> StdResult<Uint128> {
+    VESTING_INFO
+        .prefix(recipient)
+        .range(storage, None, None, Order::Ascending)
+        .try_fold(Uint128::zero(), |sum, item| {
+            item.map(|(_, vesting_info)| sum + vesting_info.delegated_amount())
+        })
+}
+
+/// ## Description
+/// Adds `weight` to the tally of the vote option `vote`.
+///
+/// ## Params
+/// - **tally** is a [`Vec`] of the vote options and their tallied weights.
+///
+/// - **vote** is an object of type [`VoteOption`] which is the vote option.
+///
+/// - **weight** is an object of type [`Uint128`] which is the weight to be added.
+fn add_vote_weight(tally: &mut Vec<(VoteOption, Uint128)>, vote: VoteOption, weight: Uint128) {
+    match tally.iter_mut().find(|(option, _)| *option == vote) {
+        Some((_, sum)) => *sum += weight,
+        None => tally.push((vote, weight)),
+    }
+}
+
+/// ## Description
+/// Returns the number of a vote option in the Cosmos SDK protobuf enum.
+///
+/// ## Params
+/// - **vote** is an object of type [`VoteOption`] which is the vote option.
+fn vote_option_number(vote: &VoteOption) -> u64 {
+    match vote {
+        VoteOption::Yes => 1,
+        VoteOption::Abstain => 2,
+        VoteOption::No => 3,
+        VoteOption::NoWithVeto => 4,
+    }
+}
+
+/// ## Description
+/// Returns the protobuf encoding of a `cosmos.gov.v1beta1.MsgVoteWeighted`.
+///
+/// ## Params
+/// - **proposal_id** is an object of type [`u64`] which is the id of the governance proposal.
+///
+/// - **voter** is an object of type [`str`] which is the address of the voter.
+///
+/// - **options** is a slice of ([`VoteOption`], [`Decimal`]) which are the weighted vote options.
+fn encode_vote_weighted(
+    proposal_id: u64,
+    voter: &str,
+    options: &[(VoteOption, Decimal)],
+) -> Binary {
+    fn encode_varint(mut value: u64, buf: &mut Vec<u8>) {
+        while value >= 0x80 {
+            buf.push((value as u8 & 0x7f) | 0x80);
+            value >>= 7;
+        }
+        buf.push(value as u8);
+    }
+    fn encode_bytes(field: u64, bytes: &[u8], buf: &mut Vec<u8>) {
+        encode_varint(field << 3 | 2, buf);
+        encode_varint(bytes.len() as u64, buf);
+        buf.extend_from_slice(bytes);
+    }
+
+    let mut buf = vec![];
+    encode_varint(1 << 3, &mut buf);
+    encode_varint(proposal_id, &mut buf);
+    encode_bytes(2, voter.as_bytes(), &mut buf);
+    for (vote, weight) in options {
+        let mut option_buf = vec![];
+        encode_varint(1 << 3, &mut option_buf);
+        encode_varint(vote_option_number(vote), &mut option_buf);
+        // Decimals are encoded as their atomics, with 18 decimal places
+        encode_bytes(2, weight.atomics().to_string().as_bytes(), &mut option_buf);
+        encode_bytes(3, &option_buf, &mut buf);
+    }
+    Binary::from(buf)
+}
+
+/// ## Description
+/// Returns the vesting id of the position which is the CW721 token `token_id`, along with its recipient.
+/// Returns a [`ContractError::TokenNotFound`] if there is no such position.
+///
+/// ## Params
+/// - **storage** is an object of type [`Storage`].
+///
+/// - **token_id** is an object of type [`str`] which is the id of the token.
+fn load_token(storage: &dyn Storage, token_id: &str) -> Result<(u64, Addr), ContractError> {
+    let not_found = || ContractError::TokenNotFound {
+        token_id: token_id.to_string(),
+    };
+    let vesting_id: u64 = token_id.parse().map_err(|_| not_found())?;
+    let recipient = VESTING_RECIPIENT
+        .may_load(storage, vesting_id)?
+        .ok_or_else(not_found)?;
+    Ok((vesting_id, recipient))
+}
+
+/// ## Description
+/// Credits the staking rewards of the delegations of a vesting and returns the messages
+/// withdrawing them, so that they remain with its recipient before the delegations change hands.
+///
+/// ## Params
+/// - **storage** is an object of type [`Storage`].
+///
+/// - **querier** is an object of type [`QuerierWrapper`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **vesting_info** is an object of type [`VestingInfo`] which is the vesting whose rewards are withdrawn.
+fn withdraw_vesting_rewards(
+    storage: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    env: &Env,
+    vesting_info: &VestingInfo,
+) -> StdResult<Vec<SubMsg>> {
+    let validators: Vec<String> = vesting_info
+        .delegations
+        .iter()
+        .map(|delegation| delegation.validator.clone())
+        .collect();
+    credit_rewards(storage, querier, env, &validators)?;
+    Ok(validators
+        .into_iter()
+        .map(|validator| {
+            SubMsg::new(CosmosMsg::Distribution(
+                DistributionMsg::WithdrawDelegatorReward { validator },
+            ))
+        })
+        .collect())
+}
+
+/// ## Description
+/// Moves the sender's own vesting position to `recipient`, along with its tollgate state and
+/// delegations. The staking rewards of the delegations are withdrawn and credited beforehand, so
+/// that they remain with the sender.
+/// Returns a [`Response`] holding the transfer messages and attributes to be merged by the caller.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **sender** is an object of type [`Addr`] which is the current recipient of the position.
+///
+/// - **recipient** is an object of type [`Addr`] which is the new recipient of the position.
+///
+/// - **token_id** is an object of type [`str`] which is the vesting id of the position.
+fn transfer_vesting(
+    deps: DepsMut,
+    env: &Env,
+    sender: &Addr,
+    recipient: &Addr,
+    token_id: &str,
+) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    if !config.transferable {
+        return Err(ContractError::NotTransferable {});
+    }
+    assert_not_paused(deps.storage, env, &config, sender)?;
+
+    let (vesting_id, owner) = load_token(deps.storage, token_id)?;
+    if owner != *sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    let vesting_info = load_vesting(deps.storage, sender, Some(vesting_id))?;
+    let mut vesting_info = reconcile_vesting(deps.storage, &deps.querier, env, vesting_info)?;
+    if vesting_info.acceptance_deadline.is_some() {
+        return Err(ContractError::PendingAcceptance {});
+    }
+    assert_no_ibc_payouts(deps.storage, vesting_id)?;
+
+    let msgs = withdraw_vesting_rewards(deps.storage, &deps.querier, env, &vesting_info)?;
+
+    remove_vesting(deps.storage, &vesting_info)?;
+    vesting_info.recipient = recipient.clone();
+    save_vesting(deps.storage, &vesting_info)?;
+    VESTING_RECIPIENT.save(deps.storage, vesting_id, recipient)?;
+
+    Ok(Response::new()
+        .add_submessages(msgs)
+        .add_attribute("sender", sender)
+        .add_attribute("recipient", recipient)
+        .add_attribute("token_id", token_id))
+}
+
+/// ## Description
+/// Transfers the sender's own vesting position, as a CW721 token, to a new recipient.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **recipient** is an object of type [`String`] which is the new recipient of the position.
+///
+/// - **token_id** is an object of type [`String`] which is the vesting id of the position.
+pub fn try_transfer_nft(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let validated_recipient = deps.api.addr_validate(&recipient)?;
+    let transfer_res = transfer_vesting(deps, &env, &info.sender, &validated_recipient, &token_id)?;
+    Ok(Response::new()
+        .add_submessages(transfer_res.messages)
+        .add_attribute("method", "try_transfer_nft")
+        .add_attributes(transfer_res.attributes))
+}
+
+/// ## Description
+/// Transfers the sender's own vesting position, as a CW721 token, to a contract and notifies it
+/// with a [`Cw721ReceiveMsg`].
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **contract** is an object of type [`String`] which is the contract receiving the position.
+///
+/// - **token_id** is an object of type [`String`] which is the vesting id of the position.
+///
+/// - **msg** is an object of type [`Binary`] which is the message passed to the contract.
+pub fn try_send_nft(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    contract: String,
+    token_id: String,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    let validated_contract = deps.api.addr_validate(&contract)?;
+    let transfer_res = transfer_vesting(deps, &env, &info.sender, &validated_contract, &token_id)?;
+    let receive_msg = ReceiverExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+        sender: info.sender.to_string(),
+        token_id,
+        msg,
+    });
+    Ok(Response::new()
+        .add_submessages(transfer_res.messages)
+        .add_submessage(SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: validated_contract.to_string(),
+            msg: to_binary(&receive_msg)?,
+            funds: vec![],
+        })))
+        .add_attribute("method", "try_send_nft")
+        .add_attributes(transfer_res.attributes))
+}
+
+/// ## Description
+/// Loads the sender's own vesting to be split or merged, which must be active and accepted.
+///
+/// ## Params
+/// - **storage** is an object of type [`Storage`].
+///
+/// - **sender** is an object of type [`Addr`] which is the recipient of the vesting.
+///
+/// - **vesting_id** is an [`Option`] of type [`u64`] which is the vesting position of the sender.
+fn load_active_vesting(
+    storage: &dyn Storage,
+    sender: &Addr,
+    vesting_id: Option<u64>,
+) -> Result<VestingInfo, ContractError> {
+    let vesting_info = load_vesting(storage, sender, vesting_id)?;
+    if !vesting_info.active {
+        return Err(ContractError::VestingNotActive {});
+    }
+    if vesting_info.acceptance_deadline.is_some() {
+        return Err(ContractError::PendingAcceptance {});
+    }
+    assert_no_ibc_payouts(storage, vesting_info.vesting_id)?;
+    Ok(vesting_info)
+}
+
+/// ## Description
+/// Returns a [`ContractError::PendingIbcPayout`] if an unresolved IBC payout was claimed from
+/// the vesting position, as its claims can only be restored to the position they came from.
+///
+/// ## Params
+/// - **storage** is an object of type [`Storage`].
+///
+/// - **vesting_id** is an object of type [`u64`] which is the vesting position to be checked.
+fn assert_no_ibc_payouts(storage: &dyn Storage, vesting_id: u64) -> Result<(), ContractError> {
+    if IBC_PAYOUT_POSITIONS
+        .prefix(vesting_id)
+        .keys(storage, None, None, Order::Ascending)
+        .next()
+        .is_some()
+    {
+        return Err(ContractError::PendingIbcPayout { vesting_id });
+    }
+    Ok(())
+}
+
+/// ## Description
+/// Carves the sender's own vesting into new positions of several recipients, pro-rata to their
+/// shares, which must all be the sender unless positions are transferable. The new positions keep the schedule, tollgate state and claimed ratio of the vesting,
+/// along with their share of its delegations, whose staking rewards remain with the sender.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **vesting_id** is an [`Option`] of type [`u64`] which is the vesting position of the sender.
+///
+/// - **recipient_shares** is a [`Vec`] of recipients and the weight of the vesting they receive.
+pub fn try_split_vesting(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    vesting_id: Option<u64>,
+    recipient_shares: Vec<(String, Uint128)>,
+) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    assert_not_paused(deps.storage, &env, &config, &info.sender)?;
+
+    if recipient_shares.is_empty() || recipient_shares.iter().any(|(_, weight)| weight.is_zero()) {
+        return Err(ContractError::InvalidShares {});
+    }
+    let recipients = recipient_shares
+        .iter()
+        .map(|(recipient, _)| deps.api.addr_validate(recipient))
+        .collect::<StdResult<Vec<Addr>>>()?;
+    // Positions which are not transferable can only be split among the sender's own positions
+    if !config.transferable && recipients.iter().any(|recipient| *recipient != info.sender) {
+        return Err(ContractError::NotTransferable {});
+    }
+    let weights: Vec<Uint128> = recipient_shares.iter().map(|(_, weight)| *weight).collect();
+
+    let vesting_info = load_active_vesting(deps.storage, &info.sender, vesting_id)?;
+    let vesting_info = reconcile_vesting(deps.storage, &deps.querier, &env, vesting_info)?;
+    // Every share must receive part of the vesting
+    let new_vestings = vesting_info.split(&weights);
+    if new_vestings
+        .iter()
+        .any(|new_vesting| new_vesting.total_amount.is_zero())
+    {
+        return Err(ContractError::InvalidShares {});
+    }
+    let msgs = withdraw_vesting_rewards(deps.storage, &deps.querier, &env, &vesting_info)?;
+
+    // The vesting is replaced by the new positions with the next vesting ids
+    remove_vesting(deps.storage, &vesting_info)?;
+    VESTING_RECIPIENT.remove(deps.storage, vesting_info.vesting_id);
+    let mut next_vesting_id = VESTING_ID.may_load(deps.storage)?.unwrap_or_default();
+    let mut new_vesting_ids: Vec<String> = vec![];
+    for (recipient, mut new_vesting) in recipients.into_iter().zip(new_vestings) {
+        next_vesting_id += 1;
+        new_vesting.recipient = recipient;
+        new_vesting.vesting_id = next_vesting_id;
+        save_vesting(deps.storage, &new_vesting)?;
+        VESTING_RECIPIENT.save(deps.storage, next_vesting_id, &new_vesting.recipient)?;
+        new_vesting_ids.push(next_vesting_id.to_string());
+    }
+    VESTING_ID.save(deps.storage, &next_vesting_id)?;
+
+    Ok(Response::new()
+        .add_submessages(msgs)
+        .add_attribute("method", "try_split_vesting")
+        .add_attribute("recipient", info.sender)
+        .add_attribute("vesting_id", vesting_info.vesting_id.to_string())
+        .add_attribute("new_vesting_ids", new_vesting_ids.join(",")))
+}
+
+/// ## Description
+/// Returns whether two vestings unlock on identical schedules with the same tollgate state, so
+/// that they can be merged.
+///
+/// ## Params
+/// - **a** is an object of type [`VestingInfo`] which is the first vesting to be compared.
+///
+/// - **b** is an object of type [`VestingInfo`] which is the second vesting to be compared.
+fn has_same_schedule(a: &VestingInfo, b: &VestingInfo) -> bool {
+    let same_mode = match (&a.mode, &b.mode) {
+        (VestingMode::Step, VestingMode::Step) | (VestingMode::Linear, VestingMode::Linear) => true,
+        // Unlock steps must unlock at the same periods / times, whatever their amounts
+        (VestingMode::Custom { steps: a_steps }, VestingMode::Custom { steps: b_steps }) => {
+            a_steps.len() == b_steps.len()
+                && a_steps.iter().zip(b_steps.iter()).all(|pair| match pair {
+                    (
+                        UnlockStep::Period { period: a, .. },
+                        UnlockStep::Period { period: b, .. },
+                    ) => a == b,
+                    (UnlockStep::Time { time: a, .. }, UnlockStep::Time { time: b, .. }) => a == b,
+                    _ => false,
+                })
+        }
+        (
+            VestingMode::Milestones {
+                tranches: a_tranches,
+            },
+            VestingMode::Milestones {
+                tranches: b_tranches,
+            },
+        ) => a_tranches.len() == b_tranches.len(),
+        _ => false,
+    };
+    same_mode
+        && a.start_time == b.start_time
+        && a.cliff_duration == b.cliff_duration
+        && a.total_periods == b.total_periods
+        && a.approved_periods == b.approved_periods
+        && a.tollgate_periods == b.tollgate_periods
+}
+
+/// ## Description
+/// Merges the sender's own vestings with identical schedules and tollgate states into the first
+/// one, summing their amounts, basket coins and delegations.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **vesting_ids** is a [`Vec`] of [`u64`] which are the vesting positions to be merged.
+pub fn try_merge_vestings(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    vesting_ids: Vec<u64>,
+) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    assert_not_paused(deps.storage, &env, &config, &info.sender)?;
+
+    let mut distinct_ids = vesting_ids.clone();
+    distinct_ids.sort_unstable();
+    distinct_ids.dedup();
+    if vesting_ids.len() < 2 || distinct_ids.len() != vesting_ids.len() {
+        return Err(ContractError::InvalidMerge {});
+    }
+
+    let vesting_info = load_active_vesting(deps.storage, &info.sender, Some(vesting_ids[0]))?;
+    let mut vesting_info = reconcile_vesting(deps.storage, &deps.querier, &env, vesting_info)?;
+    for vesting_id in &vesting_ids[1..] {
+        // The positions merged so far are reconciled with the validators they share with this one
+        let other = load_active_vesting(deps.storage, &info.sender, Some(*vesting_id))?;
+        let other = reconcile_vesting(deps.storage, &deps.querier, &env, other)?;
+        if !has_same_schedule(&vesting_info, &other) {
+            return Err(ContractError::MismatchedSchedules {});
+        }
+        remove_vesting(deps.storage, &other)?;
+        VESTING_RECIPIENT.remove(deps.storage, *vesting_id);
+        vesting_info.merge(other);
+    }
+    save_vesting(deps.storage, &vesting_info)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "try_merge_vestings")
+        .add_attribute("recipient", info.sender)
+        .add_attribute("vesting_id", vesting_info.vesting_id.to_string())
+        .add_attribute(
+            "merged_vesting_ids",
+            vesting_ids[1..]
+                .iter()
+                .map(u64::to_string)
+                .collect::<Vec<String>>()
+                .join(","),
+        )
+        .add_attribute("total_amount", vesting_info.total_amount))
+}
+
+/// ## Description
+/// Handles the forfeited amount of a vesting which was still staked when forfeited, as much as
+/// has been returned to the contract. Can be called by anyone.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **info** is an object of type [`MessageInfo`].
+///
+/// - **recipient** is an object of type [`String`] which the address of a protocol's recipient address.
+///
+/// - **vesting_id** is an [`Option`] of type [`u64`] which is the vesting position of the recipient.
+pub fn try_settle_forfeit(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: String,
+    vesting_id: Option<u64>,
+) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let validated_recipient = deps.api.addr_validate(&recipient)?;
+    assert_not_paused(deps.storage, &env, &config, &validated_recipient)?;
+    let vesting_info = load_vesting(deps.storage, &validated_recipient, vesting_id)?;
+    let mut vesting_info = reconcile_vesting(deps.storage, &deps.querier, &env, vesting_info)?;
+
+    let now = env.block.time.seconds();
+    if vesting_info.forfeit_pending.is_zero() || vesting_info.liquid_amount(now).is_zero() {
+        return Err(ContractError::NothingToSettle {});
+    }
+    vesting_info.release_unbondings(now);
+
+    let forfeit_res = forfeit_vesting(
+        deps.storage,
+        &deps.querier,
+        &env,
+        &config,
+        &mut vesting_info,
+        Uint128::zero(),
+    )?;
+    Ok(Response::new()
+        .add_submessages(forfeit_res.messages)
+        .add_attribute("method", "try_settle_forfeit")
+        .add_attribute("sender", info.sender)
+        .add_attribute("recipient", recipient)
+        .add_attribute("vesting_id", vesting_info.vesting_id.to_string())
+        .add_attributes(forfeit_res.attributes))
+}
+
+/// ## Description
+/// Exposes all the queries available in the contract.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **env** is an object of type [`Env`].
+///
+/// - **msg** is an object of type [`QueryMsg`].
+///
+/// ## Commands
+/// - **QueryMsg::VestingInfo { recipient, vesting_id }** Returns the vesting information of the
+///   specified recipient.
+///
+/// - **QueryMsg::Vestings { recipient }** Returns all vesting positions of the specified recipient.
+///
+/// - **QueryMsg::Config {}** Returns the contract settings.
+///
+/// - **QueryMsg::Roles {}** Returns the addresses granted with each role.
+///
+/// - **QueryMsg::Stats {}** Returns the aggregate statistics of all vestings.
+///
+/// - **QueryMsg::Rewards { recipient }** Returns the staking rewards credited to the specified recipient.
+///
+/// - **QueryMsg::Votes { proposal_id }** Returns the votes of recipients on the specified proposal.
+///
+/// - **QueryMsg::OwnerOf { token_id, include_expired }** Returns the recipient of a vesting position.
+///
+/// - **QueryMsg::NftInfo { token_id }** Returns a vesting position as CW721 token information.
+///
+/// - **QueryMsg::Tokens { owner, start_after, limit }** Returns the vesting ids of a recipient's
+///   positions.
+///
+/// - **QueryMsg::ClaimHook { recipient }** Returns the contract notified of the claims of the
+///   specified recipient.
+///
+/// - **QueryMsg::Credits { recipient }** Returns the failed payouts credited to the specified
+///   recipient.
+///
+/// - **QueryMsg::IbcPayouts { start_after, limit }** Returns the IBC payouts which are not
+///   resolved yet.
+///
+/// - **QueryMsg::NumTokens {}** Returns the number of vesting positions.
+///
+/// - **QueryMsg::ContractInfo {}** Returns the CW721 collection of vesting positions.
+///
+/// - **QueryMsg::Schedule { recipient, vesting_id }** Returns the unlock schedule of the specified recipient.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::VestingInfo {
+            recipient,
+            vesting_id,
+        } => to_binary(&query_vesting_info(deps, recipient, vesting_id)?),
+        QueryMsg::Vestings { recipient } => to_binary(&query_vestings(deps, recipient)?),
+        QueryMsg::Config {} => to_binary(&query_config(deps, env)?),
+        QueryMsg::Roles {} => to_binary(&query_roles(deps)?),
+        QueryMsg::Stats {} => to_binary(&STATS.load(deps.storage)?),
+        QueryMsg::Rewards { recipient } => to_binary(&query_rewards(deps, recipient)?),
+        QueryMsg::Votes { proposal_id } => to_binary(&query_votes(deps, proposal_id)?),
+        QueryMsg::OwnerOf { token_id, .. } => to_binary(&query_owner_of(deps, token_id)?),
+        QueryMsg::NftInfo { token_id } => to_binary(&query_nft_info(deps, token_id)?),
+        QueryMsg::Tokens {
+            owner,
+            start_after,
+            limit,
+        } => to_binary(&query_tokens(deps, owner, start_after, limit)?),
+        QueryMsg::ClaimHook { recipient } => to_binary(&ClaimHookResponse {
+            hook: CLAIM_HOOKS.may_load(deps.storage, &deps.api.addr_validate(&recipient)?)?,
+        }),
+        QueryMsg::Credits { recipient } => to_binary(&query_credits(deps, recipient)?),
+        QueryMsg::IbcPayouts { start_after, limit } => {
+            to_binary(&query_ibc_payouts(deps, start_after, limit)?)
+        }
+        QueryMsg::NumTokens {} => to_binary(&NumTokensResponse {
+            count: VESTING_RECIPIENT
+                .keys(deps.storage, None, None, Order::Ascending)
+                .count() as u64,
+        }),
+        QueryMsg::ContractInfo {} => to_binary(&ContractInfoResponse {
+            name: NFT_NAME.to_string(),
+            symbol: NFT_SYMBOL.to_string(),
+        }),
+        QueryMsg::Schedule {
+            recipient,
+            vesting_id,
+        } => to_binary(&query_schedule(deps, recipient, vesting_id)?),
+    }
+}
+
+/// ## Description
+/// Returns the vesting information of the specified recipient.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **recipient** is an object of type [`String`] which is the address used to query vesting information.
+///
+/// - **vesting_id** is an [`Option`] of type [`u64`] which is the vesting position of the recipient.
+fn query_vesting_info(
+    deps: Deps,
+    recipient: String,
+    vesting_id: Option<u64>,
+) -> StdResult<VestingInfo> {
+    load_vesting(
+        deps.storage,
+        &deps.api.addr_validate(&recipient)?,
+        vesting_id,
+    )
+    .map_err(|err| match err {
+        ContractError::Std(err) => err,
+        err => StdError::generic_err(err.to_string()),
+    })
+}
+
+/// ## Description
+/// Returns all vesting positions of the specified recipient.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **recipient** is an object of type [`String`] which is the address used to query vesting information.
+fn query_vestings(deps: Deps, recipient: String) -> StdResult<VestingsResponse> {
+    let vestings = VESTING_INFO
+        .prefix(&deps.api.addr_validate(&recipient)?)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (_, mut vesting_info) = item?;
+            settle_redistribution(deps.storage, &mut vesting_info)?;
+            Ok(vesting_info)
+        })
+        .collect::<StdResult<Vec<VestingInfo>>>()?;
+    Ok(VestingsResponse { vestings })
+}
+
+/// ## Description
+/// Returns the staking rewards accrued to the vestings of the specified recipient or credited to it,
+/// and not yet withdrawn.
+/// Rewards accumulated since the last withdrawal of a delegation are not included.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **recipient** is an object of type [`String`] which is the address used to query the rewards.
+fn query_rewards(deps: Deps, recipient: String) -> StdResult<RewardsResponse> {
+    let recipient = deps.api.addr_validate(&recipient)?;
+    let mut rewards = REWARDS
+        .may_load(deps.storage, &recipient)?
+        .unwrap_or_default();
+    // Along with the rewards accrued by the delegations since they were last credited
+    for item in VESTING_INFO
+        .prefix(&recipient)
+        .range(deps.storage, None, None, Order::Ascending)
+    {
+        let (vesting_id, vesting_info) = item?;
+        for delegation in vesting_info.delegations {
+            let validator_info = VALIDATORS.load(deps.storage, &delegation.validator)?;
+            let delegator_info =
+                VALIDATOR_DELEGATIONS.load(deps.storage, (&delegation.validator, vesting_id))?;
+            rewards.extend(delegator_info.pending_rewards(&validator_info));
+        }
+    }
+    Ok(RewardsResponse {
+        rewards: merge_coins(rewards),
+    })
+}
+
+/// ## Description
+/// Returns the votes of recipients on the specified governance proposal.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **proposal_id** is an object of type [`u64`] which is the id of the governance proposal.
+fn query_votes(deps: Deps, proposal_id: u64) -> StdResult<VotesResponse> {
+    let votes = VOTES
+        .prefix(proposal_id)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, vote_record)| vote_record))
+        .collect::<StdResult<Vec<VoteRecord>>>()?;
+    Ok(VotesResponse { votes })
+}
+
+/// ## Description
+/// Returns the recipient of a vesting position, as the owner of its CW721 token.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **token_id** is an object of type [`String`] which is the vesting id of the position.
+fn query_owner_of(deps: Deps, token_id: String) -> StdResult<OwnerOfResponse> {
+    let (_, owner) = load_token(deps.storage, &token_id)
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+    Ok(OwnerOfResponse {
+        owner: owner.to_string(),
+        approvals: vec![],
+    })
+}
+
+/// ## Description
+/// Returns a vesting position as the extension of its CW721 token.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **token_id** is an object of type [`String`] which is the vesting id of the position.
+fn query_nft_info(deps: Deps, token_id: String) -> StdResult<NftInfoResponse> {
+    let (vesting_id, owner) = load_token(deps.storage, &token_id)
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+    let mut vesting_info = VESTING_INFO.load(deps.storage, (&owner, vesting_id))?;
+    settle_redistribution(deps.storage, &mut vesting_info)?;
+    Ok(NftInfoResponse {
+        token_uri: None,
+        extension: vesting_info,
+    })
+}
+
+/// ## Description
+/// Returns the vesting ids of the positions of a recipient as CW721 token ids.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **owner** is an object of type [`String`] which is the address used to query the positions.
+///
+/// - **start_after** is an [`Option`] of type [`String`] which is the vesting id after which to start.
+///
+/// - **limit** is an [`Option`] of type [`u32`] which is the maximum number of token ids.
+fn query_tokens(
+    deps: Deps,
+    owner: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TokensResponse> {
+    let start_after = start_after
+        .map(|token_id| {
+            token_id
+                .parse::<u64>()
+                .map_err(|_| StdError::generic_err(format!("Invalid token id {:?}", token_id)))
+        })
+        .transpose()?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let tokens = VESTING_INFO
+        .prefix(&deps.api.addr_validate(&owner)?)
+        .keys(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|vesting_id| vesting_id.to_string()))
+        .collect::<StdResult<Vec<String>>>()?;
+    Ok(TokensResponse { tokens })
+}
+
+/// ## Description
+/// Returns the failed payouts credited to the specified recipient.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **recipient** is an object of type [`String`] which is the recipient to be queried.
+fn query_credits(deps: Deps, recipient: String) -> StdResult<CreditsResponse> {
+    let credits = CREDITS
+        .may_load(deps.storage, &deps.api.addr_validate(&recipient)?)?
+        .unwrap_or_default();
+    Ok(CreditsResponse { credits })
+}
+
+/// ## Description
+/// Returns the IBC payouts which are not resolved yet, in order of their ids.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **start_after** is an [`Option`] of type [`u64`] which is the payout id after which to start.
+///
+/// - **limit** is an [`Option`] of type [`u32`] which is the maximum number of payouts.
+fn query_ibc_payouts(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<IbcPayoutsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let payouts = IBC_PAYOUTS
+        .range(
+            deps.storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .map(|item| item.map(|(_, payout)| payout))
+        .collect::<StdResult<Vec<IbcPayout>>>()?;
+    Ok(IbcPayoutsResponse { payouts })
+}
+
+/// ## Description
+/// Returns the unlock schedule of the specified recipient, as if all its tollgates are approved.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **recipient** is an object of type [`String`] which is the address used to query the schedule.
+///
+/// - **vesting_id** is an [`Option`] of type [`u64`] which is the vesting position of the recipient.
+fn query_schedule(
+    deps: Deps,
+    recipient: String,
+    vesting_id: Option<u64>,
+) -> StdResult<ScheduleResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let clock = &config.clock;
+    let vesting_info = query_vesting_info(deps, recipient, vesting_id)?;
+    let start_time = vesting_info.start_time;
+    // Amounts accrued during the cliff unlock at once when it ends
+    let unlock_time = |time: u64| {
+        Some(std::cmp::max(
+            time,
+            start_time.saturating_add(vesting_info.cliff_duration),
+        ))
+    };
+
+    let unlocks = match &vesting_info.mode {
+        VestingMode::Step | VestingMode::Linear => {
+            let approved_vesting_info = VestingInfo {
+                approved_periods: vesting_info.total_periods,
+                ..vesting_info.clone()
+            };
+            let unlocked_at = |period: u64| {
+                unlocked_amount(
+                    &approved_vesting_info,
+                    clock,
+                    period_start(clock, start_time, period),
+                )
+            };
+            (1..=vesting_info.total_periods)
+                .map(|period| ScheduledUnlock {
+                    period,
+                    time: unlock_time(period_start(clock, start_time, period)),
+                    amount: unlocked_at(period) - unlocked_at(period - 1),
+                })
+                .collect()
+        }
+        VestingMode::Custom { steps } => steps
+            .iter()
+            .map(|step| ScheduledUnlock {
+                period: step_period(step, clock, start_time),
+                time: unlock_time(step_time(step, clock, start_time)),
+                amount: step.amount(),
+            })
+            .collect(),
+        // Milestones unlock on approval, whenever that is
+        VestingMode::Milestones { tranches } => tranches
+            .iter()
+            .enumerate()
+            .map(|(i, tranche)| ScheduledUnlock {
+                period: i as u64 + 1,
+                time: None,
+                amount: *tranche,
+            })
+            .collect(),
+    };
+
+    Ok(ScheduleResponse { unlocks })
+}
+
+/// ## Description
+/// Returns the contract settings, including the current global pause status.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+///
+/// - **env** is an object of type [`Env`].
+fn query_config(deps: Deps, env: Env) -> StdResult<ConfigResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let paused = config
+        .pause
+        .as_ref()
+        .map_or(false, |pause| pause.is_active(env.block.time.seconds()));
+    let resp = ConfigResponse {
+        forfeit_address: config.forfeit_address.to_string(),
+        forfeit_mode: config.forfeit_mode,
+        acceptance_period: config.acceptance_period,
+        claim_expiry_duration: config.claim_expiry_duration,
+        unbonding_period: config.unbonding_period,
+        transferable: config.transferable,
+        denom: config.denom,
+        vesting_start_time: config.vesting_start_time,
+        clock: config.clock,
+        paused,
+        auto_unpause_time: config.pause.and_then(|pause| pause.auto_unpause_time),
+    };
+
+    Ok(resp)
+}
+
+/// ## Description
+/// Returns the addresses granted with each role.
+///
+/// ## Params
+/// - **deps** is an object of type [`Deps`].
+fn query_roles(deps: Deps) -> StdResult<RolesResponse> {
+    let roles: Roles = ROLES.load(deps.storage)?;
+    let to_strings = |addrs: Vec<Addr>| addrs.into_iter().map(|addr| addr.to_string()).collect();
+    let resp = RolesResponse {
+        admins: to_strings(roles.admins),
+        reviewers: to_strings(roles.reviewers),
+        guardians: to_strings(roles.guardians),
+        funders: to_strings(roles.funders),
+    };
+
+    Ok(resp)
+}
+
+/// ## Description
+/// Exposes the migrate functionality in the contract. Instances of the original release, which
+/// have no roles yet, are converted to the current storage layout: `master_address` is granted
+/// all roles and receives forfeited vestings, and each recipient's vesting becomes a position
+/// with the next vesting id, starting at the contract's start time.
+///
+/// ## Params
+/// - **deps** is an object of type [`DepsMut`].
+///
+/// - **_env** is an object of type [`Env`].
+///
+/// - **_msg** is an object of type [`MigrateMsg`].
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+    let mut res = Response::new().add_attribute("method", "migrate");
+    if ROLES.may_load(deps.storage)?.is_none() {
+        let legacy_config = LEGACY_CONFIG.load(deps.storage)?;
+        CONFIG.save(
+            deps.storage,
+            &Config {
+                forfeit_address: legacy_config.master_address.clone(),
+                forfeit_mode: ForfeitMode::Transfer,
+                denom: legacy_config.denom,
+                vesting_start_time: legacy_config.vesting_start_time,
+                clock: Clock::Time,
+                pause: None,
+                acceptance_period: None,
+                claim_expiry_duration: None,
+                unbonding_period: UNBONDING_PERIOD,
+                transferable: false,
+            },
+        )?;
+        let master_address = legacy_config.master_address;
+        ROLES.save(
+            deps.storage,
+            &Roles {
+                admins: vec![master_address.clone()],
+                reviewers: vec![master_address.clone()],
+                guardians: vec![master_address.clone()],
+                funders: vec![master_address],
+            },
+        )?;
+        STATS.save(deps.storage, &Stats::default())?;
+
+        // Vestings were keyed by recipient alone, so they are rewritten under their vesting ids
+        let legacy_vestings = LEGACY_VESTING_INFO
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<(Addr, LegacyVestingInfo)>>>()?;
+        let mut vesting_id = 0u64;
+        for (recipient, legacy_vesting) in legacy_vestings {
+            vesting_id += 1;
+            LEGACY_VESTING_INFO.remove(deps.storage, &recipient);
+            let vesting_info = VestingInfo {
+                recipient: legacy_vesting.recipient,
+                vesting_id,
+                active: legacy_vesting.active,
+                approved_periods: legacy_vesting.approved_periods,
+                total_periods: legacy_vesting.total_periods,
+                last_claimed_period: legacy_vesting.last_claimed_period,
+                total_amount: legacy_vesting.total_amount,
+                claimed_amount: legacy_vesting.claimed_amount,
+                vested_amount: legacy_vesting.vested_amount,
+                amount_per_period: legacy_vesting.amount_per_period,
+                acceptance_deadline: None,
+                expired: false,
+                start_time: legacy_config.vesting_start_time,
+                cliff_duration: 0u64,
+                mode: VestingMode::Step,
+                tollgate_periods: None,
+                basket: vec![],
+                delegations: vec![],
+                unbondings: vec![],
+                forfeit_pending: Uint128::zero(),
+                redistribution_index: Decimal::zero(),
+            };
+            save_vesting(deps.storage, &vesting_info)?;
+            VESTING_RECIPIENT.save(deps.storage, vesting_id, &vesting_info.recipient)?;
+        }
+        VESTING_ID.save(deps.storage, &vesting_id)?;
+        res = res.add_attribute("migrated_vestings", vesting_id.to_string());
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    Ok(res)
 }
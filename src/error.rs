@@ -15,12 +15,32 @@ pub enum ContractError {
     #[error("Mismatched asset amount sent and distributed")]
     MismatchedAssetAmount {},
 
-    #[error("Duplicated recipients")]
-    DuplicatedRecipient {},
+    #[error("Funds sent to a message which does not take payment")]
+    UnexpectedFunds {},
+
+    #[error("Recipient has multiple vesting positions, so a vesting id must be specified")]
+    AmbiguousVesting {},
 
     #[error("Vesting amount for address {address:?} is 0")]
     ZeroVestingAmount { address: String },
 
+    #[error(
+        "Unlock steps must be non-empty, not before the start time and sum to the vesting amount"
+    )]
+    InvalidUnlockSteps {},
+
+    #[error("Basket coins must be non-zero and of distinct denoms other than the vesting denom")]
+    InvalidBasket {},
+
+    #[error("Clock must have at least one block per period")]
+    InvalidClock {},
+
+    #[error("Milestone tranches must be non-empty and sum to the vesting amount")]
+    InvalidMilestoneTranches {},
+
+    #[error("Tollgate periods must be strictly increasing and within the vesting periods")]
+    InvalidTollgatePeriods {},
+
     #[error("Nothing to be claimed")]
     NoClaimable {},
 
@@ -32,4 +52,100 @@ pub enum ContractError {
 
     #[error("Next tollgate time not reached")]
     NextTollgateTimeNotReached {},
+
+    #[error("Contract is paused")]
+    Paused {},
+
+    #[error("Contract is not paused")]
+    NotPaused {},
+
+    #[error("Auto unpause time must be in the future")]
+    InvalidAutoUnpauseTime {},
+
+    #[error("Vesting is pending acceptance")]
+    PendingAcceptance {},
+
+    #[error("Vesting is not pending acceptance")]
+    NotPendingAcceptance {},
+
+    #[error("Acceptance deadline passed")]
+    AcceptanceDeadlinePassed {},
+
+    #[error("Acceptance deadline not reached")]
+    AcceptanceDeadlineNotReached {},
+
+    #[error("Vesting expired")]
+    VestingExpired {},
+
+    #[error("Claim expiry not reached")]
+    ClaimExpiryNotReached {},
+
+    #[error("Nothing to be expired")]
+    NothingToExpire {},
+
+    #[error("Validator {validator:?} not found")]
+    ValidatorNotFound { validator: String },
+
+    #[error("Staking amount must be non-zero")]
+    ZeroStakingAmount {},
+
+    #[error("Insufficient unclaimed amount held by the contract")]
+    InsufficientLiquidAmount {},
+
+    #[error("Insufficient amount delegated to validator {validator:?}")]
+    InsufficientDelegation { validator: String },
+
+    #[error("Claimable amount is staked and must be undelegated first")]
+    ClaimableStaked {},
+
+    #[error("No forfeited amount to be settled")]
+    NothingToSettle {},
+
+    #[error("No staking rewards to be withdrawn")]
+    NoRewards {},
+
+    #[error("No delegated amount to vote with")]
+    NoDelegation {},
+
+    #[error("Vesting positions are not transferable")]
+    NotTransferable {},
+
+    #[error("Token {token_id:?} not found")]
+    TokenNotFound { token_id: String },
+
+    #[error("Claim hook {hook:?} is not a contract")]
+    InvalidClaimHook { hook: String },
+
+    #[error("No failed payouts to be retried")]
+    NoCredits {},
+
+    #[error("IBC channel and remote address must be specified")]
+    InvalidIbcClaim {},
+
+    #[error("IBC payout {payout_id} has not been refunded")]
+    IbcPayoutNotRefunded { payout_id: u64 },
+
+    #[error("Vesting {vesting_id} has unresolved IBC payouts")]
+    PendingIbcPayout { vesting_id: u64 },
+
+    #[error("Shares must be given to at least one recipient, each receiving a non-zero amount")]
+    InvalidShares {},
+
+    #[error("At least two distinct vesting positions must be merged")]
+    InvalidMerge {},
+
+    #[error("Vesting positions must have identical schedules and tollgate states to be merged")]
+    MismatchedSchedules {},
+
+    #[error("No excess funds to be swept")]
+    NoExcess {},
+
+    #[error("Role already granted to {address:?}")]
+    RoleAlreadyGranted { address: String },
+
+    #[error("Role not granted to {address:?}")]
+    RoleNotGranted { address: String },
+
+    #[error("Cannot revoke the last admin")]
+    LastAdmin {},
 }
@@ -1,4 +1,5 @@
-use crate::state::Vesting;
+use crate::state::{Clock, ForfeitMode, Role, Vesting};
+use cosmwasm_std::{Binary, Coin, Uint128, VoteOption};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -6,8 +7,24 @@ use serde::{Deserialize, Serialize};
 /// This structure stores the basic settings for creating a new vesting contract.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
-    /// Master address who can update tollgate / status of all vestings
+    /// Master address who is granted with all roles
     pub master_address: Option<String>,
+    /// Address which receives forfeited vestings; `master_address` if not specified
+    pub forfeit_address: Option<String>,
+    /// How forfeited vestings are handled; [`ForfeitMode::Transfer`] if not specified
+    pub forfeit_mode: Option<ForfeitMode>,
+    /// Period, in seconds, for recipients to accept new vestings; acceptance is not required if not specified
+    pub acceptance_period: Option<u64>,
+    /// Duration after the final period of a vesting until its unclaimed amount expires, in the
+    /// units of the clock, i.e. seconds or blocks; never expires if not specified
+    pub claim_expiry_duration: Option<u64>,
+    /// Clock which measures the vesting schedules; [`Clock::Time`] if not specified
+    pub clock: Option<Clock>,
+    /// Unbonding period, in seconds, of the chain's staking module; 21 days if not specified
+    pub unbonding_period: Option<u64>,
+    /// Whether vesting positions are CW721 tokens which can be transferred by their recipients;
+    /// not transferable if not specified
+    pub transferable: Option<bool>,
     /// A list of vestings
     pub vestings: Vec<Vesting>,
 }
@@ -18,23 +35,244 @@ pub struct InstantiateMsg {
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
     /////////////////////
-    /// MASTER CALLABLE
+    /// ADMIN CALLABLE
+    /////////////////////
+
+    /// UpdateConfig updates the contract settings.
+    UpdateConfig {
+        /// New address which receives forfeited vestings
+        forfeit_address: Option<String>,
+        /// New forfeiture mode
+        forfeit_mode: Option<ForfeitMode>,
+    },
+
+    /// GrantRole grants a role to an address.
+    GrantRole {
+        /// Role to be granted
+        role: Role,
+        /// Address to be granted with the role
+        address: String,
+    },
+
+    /// RevokeRole revokes a role from an address.
+    RevokeRole {
+        /// Role to be revoked
+        role: Role,
+        /// Address to be revoked from the role
+        address: String,
+    },
+
+    /// SweepExcess sends the funds held in excess of the outstanding vestings.
+    SweepExcess {
+        /// Denom to be swept
+        denom: String,
+        /// Address receiving the excess
+        to: String,
+    },
+
+    /// ResolveIbcPayout resolves an IBC payout once its transfer is acknowledged or timed out,
+    /// or prunes it as delivered once it has expired.
+    ResolveIbcPayout {
+        /// Id of the payout
+        payout_id: u64,
+        /// Whether the transfer was delivered; restores the claim if it was refunded
+        delivered: bool,
+    },
+
+    /////////////////////
+    /// REVIEWER CALLABLE
     /////////////////////
 
     /// ApproveTollgate either increments tollgate or deactivate a vesting.
     ApproveTollgate {
         /// Recipient address of a protocol
         recipient: String,
+        /// Vesting position of the recipient; its only position if not specified
+        vesting_id: Option<u64>,
         /// New vesting status
         approve: bool,
+        /// Whether to pay out the claimable amount to the recipient right away on approval
+        payout: Option<bool>,
+    },
+
+    /// ReclaimUnaccepted forfeits a vesting which has not been accepted before its deadline.
+    ReclaimUnaccepted {
+        /// Recipient address of a protocol
+        recipient: String,
+        /// Vesting position of the recipient; its only position if not specified
+        vesting_id: Option<u64>,
+    },
+
+    /////////////////////
+    /// GUARDIAN CALLABLE
+    /////////////////////
+
+    /// Pause halts claims and tollgate changes, either globally or for a single recipient.
+    Pause {
+        /// Recipient address of a protocol to freeze; pauses the whole contract if not specified
+        recipient: Option<String>,
+        /// Time, in the future, when the pause is automatically lifted; never if not specified
+        auto_unpause_time: Option<u64>,
+    },
+
+    /// Unpause lifts a pause, either global or for a single recipient.
+    Unpause {
+        /// Recipient address of a protocol to unfreeze; unpauses the whole contract if not specified
+        recipient: Option<String>,
+    },
+
+    /////////////////////
+    /// FUNDER CALLABLE
+    /////////////////////
+
+    /// AddVestings adds new vestings, funded by the sent tokens.
+    AddVestings {
+        /// A list of vestings
+        vestings: Vec<Vesting>,
+    },
+
+    /// TopUpVesting increases the total amount of an active vesting by the sent tokens.
+    TopUpVesting {
+        /// Recipient address of a protocol
+        recipient: String,
+        /// Vesting position of the recipient; its only position if not specified
+        vesting_id: Option<u64>,
     },
 
     /////////////////////
     /// USER CALLABLE
     /////////////////////
 
-    /// Claim unlocked vesting
-    Claim {},
+    /// Claim unlocked vesting across the sender's vesting positions
+    Claim {
+        /// Vesting positions to claim from; all positions if not specified
+        vesting_ids: Option<Vec<u64>>,
+        /// IBC transfer paying out the claim to another chain; sent to the sender if not specified
+        ibc: Option<IbcClaim>,
+    },
+
+    /// AcceptAllocation accepts the sender's vesting which is pending acceptance.
+    AcceptAllocation {
+        /// Vesting position of the sender; its only position if not specified
+        vesting_id: Option<u64>,
+    },
+
+    /// Renounce deactivates the sender's own vesting and forfeits its remainder.
+    Renounce {
+        /// Vesting position of the sender; its only position if not specified
+        vesting_id: Option<u64>,
+        /// Whether to pay out the periods already earned before forfeiting
+        keep_claimable: bool,
+    },
+
+    /// SplitVesting carves the sender's own vesting into new positions of several recipients,
+    /// which keep its schedule, tollgate state and claimed ratio.
+    SplitVesting {
+        /// Vesting position of the sender; its only position if not specified
+        vesting_id: Option<u64>,
+        /// Recipients of the new positions, with the weight of the vesting they receive. Each
+        /// receives the fraction of its weight over the sum of the weights, which are integers so
+        /// that the fractions always sum to one
+        recipient_shares: Vec<(String, Uint128)>,
+    },
+
+    /// MergeVestings merges the sender's own vestings with identical schedules into the first one.
+    MergeVestings {
+        /// Vesting positions of the sender to be merged
+        vesting_ids: Vec<u64>,
+    },
+
+    /// Delegate stakes the unclaimed amount of the sender's own vesting to a validator.
+    Delegate {
+        /// Vesting position of the sender; its only position if not specified
+        vesting_id: Option<u64>,
+        /// Operator address of the validator
+        validator: String,
+        /// Amount to be delegated
+        amount: Uint128,
+    },
+
+    /// Redelegate moves a delegation of the sender's own vesting to another validator.
+    Redelegate {
+        /// Vesting position of the sender; its only position if not specified
+        vesting_id: Option<u64>,
+        /// Operator address of the validator currently delegated to
+        src_validator: String,
+        /// Operator address of the validator to be delegated to
+        dst_validator: String,
+        /// Amount to be redelegated
+        amount: Uint128,
+    },
+
+    /// Undelegate unbonds a delegation of the sender's own vesting, which becomes claimable once released.
+    Undelegate {
+        /// Vesting position of the sender; its only position if not specified
+        vesting_id: Option<u64>,
+        /// Operator address of the validator
+        validator: String,
+        /// Amount to be undelegated
+        amount: Uint128,
+    },
+
+    /// WithdrawRewards withdraws the staking rewards of the sender's delegations and sends the
+    /// rewards credited to the sender.
+    WithdrawRewards {},
+
+    /// RetryPayout sends the failed payouts credited to the sender.
+    RetryPayout {},
+
+    /// SetClaimHook registers a contract to be sent the sender's claims with a [`VestingClaimHook`].
+    SetClaimHook {
+        /// Contract notified of the claims; removes the hook if not specified
+        hook: Option<String>,
+    },
+
+    /// Vote has the contract vote on a governance proposal with the amount delegated by the sender's vestings.
+    Vote {
+        /// Id of the governance proposal
+        proposal_id: u64,
+        /// Vote option
+        vote: VoteOption,
+    },
+
+    /// TransferNft transfers the sender's own vesting position, as a CW721 token, to a new recipient.
+    TransferNft {
+        /// New recipient of the vesting position
+        recipient: String,
+        /// Vesting id of the position
+        token_id: String,
+    },
+
+    /// SendNft transfers the sender's own vesting position, as a CW721 token, to a contract and
+    /// notifies it with a [`Cw721ReceiveMsg`].
+    SendNft {
+        /// Contract which becomes the new recipient of the vesting position
+        contract: String,
+        /// Vesting id of the position
+        token_id: String,
+        /// Message passed to the contract
+        msg: Binary,
+    },
+
+    /////////////////////
+    /// ANYONE CALLABLE
+    /////////////////////
+
+    /// ExpireVesting sweeps the unclaimed amount of an expired vesting to the forfeit destination.
+    ExpireVesting {
+        /// Recipient address of a protocol
+        recipient: String,
+        /// Vesting position of the recipient; its only position if not specified
+        vesting_id: Option<u64>,
+    },
+
+    /// SettleForfeit handles the forfeited amount of a vesting which was staked once it is unbonded.
+    SettleForfeit {
+        /// Recipient address of a protocol
+        recipient: String,
+        /// Vesting position of the recipient; its only position if not specified
+        vesting_id: Option<u64>,
+    },
 }
 
 /// ## Description
@@ -46,9 +284,159 @@ pub enum QueryMsg {
     VestingInfo {
         /// Recipient address of a protocol
         recipient: String,
+        /// Vesting position of the recipient; its only position if not specified
+        vesting_id: Option<u64>,
     },
 
+    /// Vestings returns all vesting positions of the specified recipient
+    Vestings {
+        /// Recipient address of a protocol
+        recipient: String,
+    },
+
+    /// Config returns the contract settings
     Config {},
+
+    /// Roles returns the addresses granted with each role
+    Roles {},
+
+    /// Stats returns the aggregate statistics of all vestings
+    Stats {},
+
+    /// Rewards returns the staking rewards credited to the specified recipient
+    Rewards {
+        /// Recipient address of a protocol
+        recipient: String,
+    },
+
+    /// Votes returns the votes of recipients on the specified governance proposal
+    Votes {
+        /// Id of the governance proposal
+        proposal_id: u64,
+    },
+
+    /// OwnerOf returns the recipient of a vesting position, as the owner of its CW721 token
+    OwnerOf {
+        /// Vesting id of the position
+        token_id: String,
+        /// Ignored, as approvals are not supported
+        include_expired: Option<bool>,
+    },
+
+    /// NftInfo returns a vesting position as the extension of its CW721 token
+    NftInfo {
+        /// Vesting id of the position
+        token_id: String,
+    },
+
+    /// Tokens returns the vesting ids of the positions of a recipient as CW721 token ids
+    Tokens {
+        /// Recipient address of a protocol
+        owner: String,
+        /// Vesting id after which to start
+        start_after: Option<String>,
+        /// Maximum number of token ids
+        limit: Option<u32>,
+    },
+
+    /// ClaimHook returns the contract notified of the claims of the specified recipient
+    ClaimHook {
+        /// Recipient address of a protocol
+        recipient: String,
+    },
+
+    /// Credits returns the failed payouts credited to the specified recipient
+    Credits {
+        /// Recipient address of a protocol
+        recipient: String,
+    },
+
+    /// IbcPayouts returns the IBC payouts which are not resolved yet
+    IbcPayouts {
+        /// Payout id after which to start
+        start_after: Option<u64>,
+        /// Maximum number of payouts
+        limit: Option<u32>,
+    },
+
+    /// NumTokens returns the number of vesting positions
+    NumTokens {},
+
+    /// ContractInfo returns the CW721 collection of vesting positions
+    ContractInfo {},
+
+    /// Schedule returns the unlock schedule of the specified recipient
+    Schedule {
+        /// Recipient address of a protocol
+        recipient: String,
+        /// Vesting position of the recipient; its only position if not specified
+        vesting_id: Option<u64>,
+    },
+}
+
+/// ## Description
+/// This structure describes an IBC transfer paying out a claim to another chain.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IbcClaim {
+    /// Channel to transfer the claimed coins over
+    pub channel_id: String,
+    /// Address on the remote chain receiving the claimed coins
+    pub remote_address: String,
+    /// Timeout, in seconds, of the transfer; 1 hour if not specified
+    pub timeout: Option<u64>,
+}
+
+/// ## Description
+/// This structure describes the notification sent to a contract receiving a vesting position.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Cw721ReceiveMsg {
+    /// Previous recipient of the vesting position
+    pub sender: String,
+    /// Vesting id of the position
+    pub token_id: String,
+    /// Message passed by the previous recipient
+    pub msg: Binary,
+}
+
+/// ## Description
+/// This structure describes the execute messages of a contract receiving a vesting position.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiverExecuteMsg {
+    /// ReceiveNft notifies the contract that it became the recipient of a vesting position
+    ReceiveNft(Cw721ReceiveMsg),
+}
+
+/// ## Description
+/// This structure describes the notification of a claim sent to the claim hook of its recipient,
+/// along with the claimed coins.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct VestingClaimHook {
+    /// Claimed coins, including the basket coins
+    pub amount: Vec<Coin>,
+    /// Claimed positions, with the eligible periods each of them reached
+    pub positions: Vec<ClaimedPosition>,
+}
+
+/// ## Description
+/// This structure describes a vesting position claimed from, as notified to a claim hook.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClaimedPosition {
+    /// Id of the vesting position
+    pub vesting_id: u64,
+    /// Eligible periods reached by the position
+    pub periods: u64,
+}
+
+/// ## Description
+/// This structure describes the execute messages of a claim hook contract.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaimHookExecuteMsg {
+    /// VestingClaimHook notifies the contract of a claim of its recipient
+    VestingClaimHook(VestingClaimHook),
 }
 
 /// ## Description
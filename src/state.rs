@@ -2,41 +2,240 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cosmwasm_std::Addr;
+use cosmwasm_std::Coin;
+use cosmwasm_std::Decimal;
+use cosmwasm_std::Empty;
+use cosmwasm_std::Timestamp;
 use cosmwasm_std::Uint128;
+use cosmwasm_std::VoteOption;
 use cw_storage_plus::{Item, Map};
 
 //////////////////////////////////////////////////////////////////////
-/// CONFIG
+// CONFIG
 //////////////////////////////////////////////////////////////////////
 
 /// ## Description
 /// This structure holds the contract parameters.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
-    /// Master address who can update tollgate / status of all vestings
-    pub master_address: Addr,
+    /// Address which receives forfeited vestings in [`ForfeitMode::Transfer`]
+    pub forfeit_address: Addr,
+    /// How forfeited vestings are handled
+    pub forfeit_mode: ForfeitMode,
     /// Specific vesting denom
     pub denom: String,
-    /// Start time of this vesting contract, i.e. contract init time
+    /// Start time of this vesting contract, i.e. contract init time, and the default start time of vestings
     pub vesting_start_time: u64,
+    /// Clock which measures the periods of all vestings
+    pub clock: Clock,
+    /// Global pause of claims and tollgate changes, if any
+    pub pause: Option<PauseInfo>,
+    /// Period, in seconds, for recipients to accept new vestings; acceptance is not required if not set
+    pub acceptance_period: Option<u64>,
+    /// Duration after the final period of a vesting until its unclaimed amount expires, in the
+    /// units of the clock, i.e. seconds or blocks; never expires if not set
+    pub claim_expiry_duration: Option<u64>,
+    /// Unbonding period, in seconds, of the chain's staking module
+    pub unbonding_period: u64,
+    /// Whether vesting positions are CW721 tokens which can be transferred by their recipients
+    pub transferable: bool,
 }
 
 /// ## Description
 /// A custom struct for each query response that returns general contract settings/configs.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct ConfigResponse {
-    /// Master address who can update tollgate / status of all vestings
-    pub master_address: String,
+    /// Address which receives forfeited vestings in [`ForfeitMode::Transfer`]
+    pub forfeit_address: String,
+    /// How forfeited vestings are handled
+    pub forfeit_mode: ForfeitMode,
+    /// Period, in seconds, for recipients to accept new vestings; acceptance is not required if not set
+    pub acceptance_period: Option<u64>,
+    /// Duration after the final period of a vesting until its unclaimed amount expires, in the
+    /// units of the clock, i.e. seconds or blocks; never expires if not set
+    pub claim_expiry_duration: Option<u64>,
+    /// Unbonding period, in seconds, of the chain's staking module
+    pub unbonding_period: u64,
+    /// Whether vesting positions are CW721 tokens which can be transferred by their recipients
+    pub transferable: bool,
     /// Specific vesting denom
     pub denom: String,
-    /// Start time of this vesting contract, i.e. contract init time
+    /// Start time of this vesting contract, i.e. contract init time, and the default start time of vestings
     pub vesting_start_time: u64,
+    /// Clock which measures the periods of all vestings
+    pub clock: Clock,
+    /// Whether claims and tollgate changes are globally paused
+    pub paused: bool,
+    /// Time when the global pause is automatically lifted, if any
+    pub auto_unpause_time: Option<u64>,
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
 
+/// ## Description
+/// This enum describes the clock which measures the vesting schedules. Start times, cliffs, unlock
+/// step times and claim expiries are all expressed in its units.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Clock {
+    /// Block time, in seconds, with fixed length periods
+    Time,
+    /// Block height, with `blocks_per_period` blocks in each period
+    Height { blocks_per_period: u64 },
+    /// Block time, in seconds, with periods ending on the same day of each calendar month (UTC)
+    /// as the start time
+    CalendarMonth,
+}
+
+/// ## Description
+/// This enum describes how the unvested remainder of a forfeited vesting is handled.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ForfeitMode {
+    /// Send the remainder to `forfeit_address`
+    Transfer,
+    /// Redistribute the remainder pro-rata to the other active vestings
+    Redistribute,
+    /// Burn the remainder
+    Burn,
+}
+
+//////////////////////////////////////////////////////////////////////
+// STATS
+//////////////////////////////////////////////////////////////////////
+
+/// ## Description
+/// This structure holds the aggregate statistics of all vestings.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct Stats {
+    /// Total amount forfeited from disapproved, renounced, reclaimed or expired vestings
+    pub total_forfeited: Uint128,
+    /// Part of `total_forfeited` which has been burned
+    pub total_burned: Uint128,
+    /// Part of `total_forfeited` which expired unclaimed
+    pub total_expired: Uint128,
+}
+
+pub const STATS: Item<Stats> = Item::new("stats");
+
+//////////////////////////////////////////////////////////////////////
+// REDISTRIBUTION
+//////////////////////////////////////////////////////////////////////
+
+/// ## Description
+/// This structure holds the state of the redistribution of forfeited vestings, which is credited
+/// to the eligible vestings lazily, pro-rata to their total amounts.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct Redistribution {
+    /// Cumulative amount redistributed per unit of total amount of the eligible vestings
+    pub index: Decimal,
+    /// Sum of the total amounts of the eligible vestings
+    pub total_weight: Uint128,
+}
+
+pub const REDISTRIBUTION: Item<Redistribution> = Item::new("redistribution");
+
+//////////////////////////////////////////////////////////////////////
+// PAUSE
+//////////////////////////////////////////////////////////////////////
+
+/// ## Description
+/// This structure holds the parameters of a pause, either global or per recipient.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PauseInfo {
+    /// Time when the pause is automatically lifted, if any
+    pub auto_unpause_time: Option<u64>,
+}
+
+impl PauseInfo {
+    /// Returns whether the pause is still in effect at `time`
+    pub fn is_active(&self, time: u64) -> bool {
+        self.auto_unpause_time
+            .map_or(true, |auto_unpause_time| time < auto_unpause_time)
+    }
+}
+
+/// Recipients whose claims and tollgate changes are frozen
+pub const FROZEN_RECIPIENTS: Map<&Addr, PauseInfo> = Map::new("frozen_recipients");
+
+//////////////////////////////////////////////////////////////////////
+// ROLES
+//////////////////////////////////////////////////////////////////////
+
+/// ## Description
+/// This enum describes the roles that can be granted to an address.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Can update the config and grant / revoke roles
+    Admin,
+    /// Can approve or disapprove tollgates
+    Reviewer,
+    /// Can pause the contract
+    Guardian,
+    /// Can add new vestings or top up existing ones
+    Funder,
+}
+
+/// ## Description
+/// This structure holds the addresses granted with each role.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct Roles {
+    /// Addresses with the admin role
+    pub admins: Vec<Addr>,
+    /// Addresses with the reviewer role
+    pub reviewers: Vec<Addr>,
+    /// Addresses with the guardian role
+    pub guardians: Vec<Addr>,
+    /// Addresses with the funder role
+    pub funders: Vec<Addr>,
+}
+
+impl Roles {
+    /// Returns the addresses granted with `role`
+    pub fn members(&self, role: Role) -> &Vec<Addr> {
+        match role {
+            Role::Admin => &self.admins,
+            Role::Reviewer => &self.reviewers,
+            Role::Guardian => &self.guardians,
+            Role::Funder => &self.funders,
+        }
+    }
+
+    /// Returns a mutable reference to the addresses granted with `role`
+    pub fn members_mut(&mut self, role: Role) -> &mut Vec<Addr> {
+        match role {
+            Role::Admin => &mut self.admins,
+            Role::Reviewer => &mut self.reviewers,
+            Role::Guardian => &mut self.guardians,
+            Role::Funder => &mut self.funders,
+        }
+    }
+
+    /// Returns whether `addr` has been granted with `role`
+    pub fn has(&self, role: Role, addr: &Addr) -> bool {
+        self.members(role).contains(addr)
+    }
+}
+
+/// ## Description
+/// A custom struct for each query response that returns the addresses of each role.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RolesResponse {
+    /// Addresses with the admin role
+    pub admins: Vec<String>,
+    /// Addresses with the reviewer role
+    pub reviewers: Vec<String>,
+    /// Addresses with the guardian role
+    pub guardians: Vec<String>,
+    /// Addresses with the funder role
+    pub funders: Vec<String>,
+}
+
+pub const ROLES: Item<Roles> = Item::new("roles");
+
 //////////////////////////////////////////////////////////////////////
-/// VESTING
+// VESTING
 //////////////////////////////////////////////////////////////////////
 
 /// ## Description
@@ -47,16 +246,788 @@ pub struct Vesting {
     pub recipient: String,
     /// Vesting amount
     pub amount: Uint128,
+    /// Start time of the vesting, which can be in the future; the time it is added if not specified
+    pub start_time: Option<u64>,
+    /// Duration from the start time during which nothing can be claimed, in the units of the
+    /// clock, i.e. seconds or blocks
+    pub cliff_duration: Option<u64>,
+    /// How the vesting unlocks over time; [`VestingMode::Step`] if not specified
+    pub mode: Option<VestingMode>,
+    /// Periods, counted from the start time, at which tollgates are placed;
+    /// every [`crate::contract::PERIODS_PER_TOLL`] periods and at the end of the vesting if not
+    /// specified
+    pub tollgate_periods: Option<Vec<u64>>,
+    /// Coins of other denoms vesting on the same schedule as `amount`
+    pub basket: Option<Vec<Coin>>,
+}
+
+/// ## Description
+/// This enum describes how a vesting unlocks over time.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VestingMode {
+    /// `amount_per_period` unlocks at the end of each period
+    Step,
+    /// The total amount unlocks continuously per second over all periods
+    Linear,
+    /// Explicit unlock steps which sum to the total amount
+    Custom { steps: Vec<UnlockStep> },
+    /// Tranches which sum to the total amount, each unlocked by an approved tollgate with no
+    /// time component
+    Milestones { tranches: Vec<Uint128> },
+}
+
+/// ## Description
+/// This enum describes a single step of a custom unlock schedule.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UnlockStep {
+    /// Unlocks `amount` once `period` periods have passed since the start time
+    Period { period: u64, amount: Uint128 },
+    /// Unlocks `amount` at `time`
+    Time { time: u64, amount: Uint128 },
+}
+
+impl UnlockStep {
+    /// Returns the amount unlocked by the step
+    pub fn amount(&self) -> Uint128 {
+        match self {
+            UnlockStep::Period { amount, .. } | UnlockStep::Time { amount, .. } => *amount,
+        }
+    }
+
+    /// Returns a mutable reference to the amount unlocked by the step
+    pub fn amount_mut(&mut self) -> &mut Uint128 {
+        match self {
+            UnlockStep::Period { amount, .. } | UnlockStep::Time { amount, .. } => amount,
+        }
+    }
 }
 
 //////////////////////////////////////////////////////////////////////
-/// VESTING INFO
+// VESTING INFO
 //////////////////////////////////////////////////////////////////////
 
 /// ## Description
 /// This structure holds the vesting information of each protocol.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct VestingInfo {
+    /// The address of the recipient protocol to approve the tollgate for
+    pub recipient: Addr,
+    /// Id of the vesting position, unique across all recipients
+    pub vesting_id: u64,
+    /// Vesting valid status
+    pub active: bool,
+    /// Current approved tollgates, in periods
+    pub approved_periods: u64,
+    /// Total vesting periods
+    pub total_periods: u64,
+    /// Previously claimed period, start at 0
+    pub last_claimed_period: u64,
+    /// Total vesting amount
+    pub total_amount: Uint128,
+    /// Claimed vesting amount
+    pub claimed_amount: Uint128,
+    /// Unclaimed amount
+    pub vested_amount: Uint128,
+    /// Claimable amount for each period
+    pub amount_per_period: Uint128,
+    /// Deadline for the recipient to accept the vesting, set while pending acceptance
+    pub acceptance_deadline: Option<u64>,
+    /// Whether the unclaimed amount expired and has been swept
+    pub expired: bool,
+    /// Start time of the vesting, from which its periods are measured
+    pub start_time: u64,
+    /// Duration from the start time during which nothing can be claimed, in the units of the
+    /// clock, i.e. seconds or blocks
+    pub cliff_duration: u64,
+    /// How the vesting unlocks over time
+    pub mode: VestingMode,
+    /// Periods, counted from the start time, at which tollgates are placed, if configured
+    pub tollgate_periods: Option<Vec<u64>>,
+    /// Coins of other denoms vesting on the same schedule, pro-rata to the vesting denom
+    pub basket: Vec<BasketCoin>,
+    /// Delegations of the unclaimed amount made on behalf of the recipient
+    pub delegations: Vec<Delegation>,
+    /// Undelegations of the unclaimed amount which are still unbonding
+    pub unbondings: Vec<Unbonding>,
+    /// Forfeited amount which is still staked, to be settled once it is liquid
+    pub forfeit_pending: Uint128,
+    /// Redistribution index up to which forfeited vestings have been credited to the vesting
+    pub redistribution_index: Decimal,
+}
+
+/// ## Description
+/// This structure describes a delegation of a vesting to a validator.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Delegation {
+    /// Operator address of the validator
+    pub validator: String,
+    /// Delegated amount
+    pub amount: Uint128,
+}
+
+/// ## Description
+/// This structure describes an undelegation of a vesting which is unbonding.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Unbonding {
+    /// Unbonding amount
+    pub amount: Uint128,
+    /// Block time, in seconds, when the amount is returned to the contract
+    pub release_time: u64,
+}
+
+/// ## Description
+/// This structure describes a coin of another denom vesting alongside the vesting denom.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BasketCoin {
+    /// Denom of the coin
+    pub denom: String,
+    /// Total vesting amount
+    pub total_amount: Uint128,
+    /// Amount unlocked for each period
+    pub amount_per_period: Uint128,
+    /// Amount of vested coins claimed
+    pub claimed_amount: Uint128,
+    /// Amount of vested coins still unclaimed
+    pub vested_amount: Uint128,
+}
+
+impl VestingInfo {
+    /// Increases the total amount by `amount`, spreading it evenly across all periods,
+    /// or pro-rata across the unlock steps / tranches of a custom / milestone schedule
+    pub fn top_up(&mut self, amount: Uint128) {
+        let step_amounts: Vec<&mut Uint128> = match &mut self.mode {
+            VestingMode::Custom { steps } => steps.iter_mut().map(UnlockStep::amount_mut).collect(),
+            VestingMode::Milestones { tranches } => tranches.iter_mut().collect(),
+            VestingMode::Step | VestingMode::Linear => vec![],
+        };
+        // The rounding remainder goes to the last step so that the steps sum to the total
+        let mut remaining_amount = amount;
+        let num_steps = step_amounts.len();
+        for (i, step_amount) in step_amounts.into_iter().enumerate() {
+            let share = if i + 1 == num_steps {
+                remaining_amount
+            } else {
+                amount.multiply_ratio(*step_amount, self.total_amount)
+            };
+            remaining_amount -= share;
+            *step_amount += share;
+        }
+        self.total_amount += amount;
+        self.vested_amount += amount;
+        self.amount_per_period = self.total_amount / Uint128::from(self.total_periods);
+    }
+
+    /// Claims `claimable_amount` of the vesting denom up to `eligible_periods`, along with the
+    /// matching share of each basket coin. Returns the basket coins claimed
+    pub fn claim(&mut self, eligible_periods: u64, claimable_amount: Uint128) -> Vec<Coin> {
+        self.claimed_amount += claimable_amount;
+        self.vested_amount -= claimable_amount;
+        self.last_claimed_period = eligible_periods;
+
+        let (claimed_amount, total_amount) = (self.claimed_amount, self.total_amount);
+        self.basket
+            .iter_mut()
+            .map(|basket_coin| {
+                let amount = basket_coin
+                    .total_amount
+                    .multiply_ratio(claimed_amount, total_amount)
+                    .saturating_sub(basket_coin.claimed_amount);
+                basket_coin.claimed_amount += amount;
+                basket_coin.vested_amount -= amount;
+                Coin::new(amount.u128(), basket_coin.denom.clone())
+            })
+            .collect()
+    }
+
+    /// Releases the basket coins which are no longer backed by the claimed and still vested
+    /// amounts of the vesting denom, once these are reduced by a forfeiture.
+    /// Returns the basket coins released
+    pub fn forfeit_basket(&mut self) -> Vec<Coin> {
+        let (backed_amount, total_amount) =
+            (self.claimed_amount + self.vested_amount, self.total_amount);
+        self.basket
+            .iter_mut()
+            .map(|basket_coin| {
+                let kept_amount = basket_coin
+                    .total_amount
+                    .multiply_ratio(backed_amount, total_amount)
+                    .saturating_sub(basket_coin.claimed_amount);
+                let amount = basket_coin.vested_amount.saturating_sub(kept_amount);
+                basket_coin.vested_amount -= amount;
+                Coin::new(amount.u128(), basket_coin.denom.clone())
+            })
+            .collect()
+    }
+
+    /// Returns the amount delegated to validators
+    pub fn delegated_amount(&self) -> Uint128 {
+        self.delegations
+            .iter()
+            .fold(Uint128::zero(), |sum, delegation| sum + delegation.amount)
+    }
+
+    /// Returns the amount delegated or still unbonding at `time`, in seconds
+    pub fn staked_amount(&self, time: u64) -> Uint128 {
+        self.unbondings
+            .iter()
+            .filter(|unbonding| unbonding.release_time > time)
+            .fold(self.delegated_amount(), |sum, unbonding| {
+                sum + unbonding.amount
+            })
+    }
+
+    /// Returns the amount held by the contract for the vesting at `time`, in seconds,
+    /// including the forfeited amount pending settlement
+    pub fn liquid_amount(&self, time: u64) -> Uint128 {
+        (self.vested_amount + self.forfeit_pending).saturating_sub(self.staked_amount(time))
+    }
+
+    /// Returns the amount which can be paid out to the recipient at `time`, in seconds,
+    /// as the forfeited amount pending settlement is paid first
+    pub fn payable_amount(&self, time: u64) -> Uint128 {
+        self.liquid_amount(time)
+            .saturating_sub(self.forfeit_pending)
+    }
+
+    /// Books a slashing loss of `amount` delegated to `validator`, which lowers the delegation
+    /// along with the unclaimed amount, then the forfeited amount pending settlement
+    pub fn slash(&mut self, validator: &str, amount: Uint128) {
+        for delegation in self.delegations.iter_mut() {
+            if delegation.validator == validator {
+                delegation.amount = delegation.amount.saturating_sub(amount);
+            }
+        }
+        self.delegations
+            .retain(|delegation| !delegation.amount.is_zero());
+        let vested_loss = std::cmp::min(amount, self.vested_amount);
+        self.vested_amount -= vested_loss;
+        self.forfeit_pending = self.forfeit_pending.saturating_sub(amount - vested_loss);
+    }
+
+    /// Returns the weight of the vesting in redistributions, i.e. its total amount while it is
+    /// active, accepted and not fully claimed
+    pub fn redistribution_weight(&self) -> Uint128 {
+        if self.active && self.acceptance_deadline.is_none() && !self.vested_amount.is_zero() {
+            self.total_amount
+        } else {
+            Uint128::zero()
+        }
+    }
+
+    /// Returns the share of the redistributions up to `index` not yet credited to the vesting
+    pub fn redistribution_share(&self, index: Decimal) -> Uint128 {
+        self.redistribution_weight() * (index - self.redistribution_index)
+    }
+
+    /// Removes the undelegations which have been released by `time`, in seconds
+    pub fn release_unbondings(&mut self, time: u64) {
+        self.unbondings
+            .retain(|unbonding| unbonding.release_time > time);
+    }
+
+    /// Splits the vesting into one position per weight, pro-rata to the weights, which keep the
+    /// same schedule, tollgate state and claimed ratio. Each amount is split with the rounding
+    /// remainder going to the last position so that the totals are conserved
+    pub fn split(&self, weights: &[Uint128]) -> Vec<VestingInfo> {
+        let claimed_amounts = split_amount(self.claimed_amount, weights);
+        let vested_amounts = split_amount(self.vested_amount, weights);
+        let forfeit_pendings = split_amount(self.forfeit_pending, weights);
+        let basket_amounts: Vec<(Vec<Uint128>, Vec<Uint128>)> = self
+            .basket
+            .iter()
+            .map(|basket_coin| {
+                (
+                    split_amount(basket_coin.claimed_amount, weights),
+                    split_amount(basket_coin.vested_amount, weights),
+                )
+            })
+            .collect();
+        let delegation_amounts: Vec<Vec<Uint128>> = self
+            .delegations
+            .iter()
+            .map(|delegation| split_amount(delegation.amount, weights))
+            .collect();
+        let unbonding_amounts: Vec<Vec<Uint128>> = self
+            .unbondings
+            .iter()
+            .map(|unbonding| split_amount(unbonding.amount, weights))
+            .collect();
+
+        (0..weights.len())
+            .map(|i| {
+                let total_amount = claimed_amounts[i] + vested_amounts[i];
+                // The steps of each position are rounded cumulatively so that they sum to its total
+                let mut mode = self.mode.clone();
+                let step_amounts: Vec<&mut Uint128> = match &mut mode {
+                    VestingMode::Custom { steps } => {
+                        steps.iter_mut().map(UnlockStep::amount_mut).collect()
+                    }
+                    VestingMode::Milestones { tranches } => tranches.iter_mut().collect(),
+                    VestingMode::Step | VestingMode::Linear => vec![],
+                };
+                let (mut cumulative_amount, mut cumulative_share) =
+                    (Uint128::zero(), Uint128::zero());
+                for step_amount in step_amounts {
+                    cumulative_amount += *step_amount;
+                    let share = cumulative_amount.multiply_ratio(total_amount, self.total_amount);
+                    *step_amount = share - cumulative_share;
+                    cumulative_share = share;
+                }
+
+                VestingInfo {
+                    total_amount,
+                    claimed_amount: claimed_amounts[i],
+                    vested_amount: vested_amounts[i],
+                    amount_per_period: total_amount / Uint128::from(self.total_periods),
+                    mode,
+                    basket: self
+                        .basket
+                        .iter()
+                        .zip(basket_amounts.iter())
+                        .map(|(basket_coin, (claimed_amounts, vested_amounts))| {
+                            let total_amount = claimed_amounts[i] + vested_amounts[i];
+                            BasketCoin {
+                                denom: basket_coin.denom.clone(),
+                                total_amount,
+                                amount_per_period: total_amount / Uint128::from(self.total_periods),
+                                claimed_amount: claimed_amounts[i],
+                                vested_amount: vested_amounts[i],
+                            }
+                        })
+                        .collect(),
+                    delegations: self
+                        .delegations
+                        .iter()
+                        .zip(delegation_amounts.iter())
+                        .filter(|(_, amounts)| !amounts[i].is_zero())
+                        .map(|(delegation, amounts)| Delegation {
+                            validator: delegation.validator.clone(),
+                            amount: amounts[i],
+                        })
+                        .collect(),
+                    unbondings: self
+                        .unbondings
+                        .iter()
+                        .zip(unbonding_amounts.iter())
+                        .filter(|(_, amounts)| !amounts[i].is_zero())
+                        .map(|(unbonding, amounts)| Unbonding {
+                            amount: amounts[i],
+                            release_time: unbonding.release_time,
+                        })
+                        .collect(),
+                    forfeit_pending: forfeit_pendings[i],
+                    ..self.clone()
+                }
+            })
+            .collect()
+    }
+
+    /// Restores `amount`, which was claimed but could not be paid out, as unclaimed
+    pub fn unclaim(&mut self, amount: &Coin, vesting_denom: &str) {
+        if amount.denom == vesting_denom {
+            self.claimed_amount -= amount.amount;
+            self.vested_amount += amount.amount;
+        } else if let Some(basket_coin) = self
+            .basket
+            .iter_mut()
+            .find(|basket_coin| basket_coin.denom == amount.denom)
+        {
+            basket_coin.claimed_amount -= amount.amount;
+            basket_coin.vested_amount += amount.amount;
+        }
+    }
+
+    /// Merges `other`, which must have the same schedule, into the vesting by summing their
+    /// amounts, unlock steps, basket coins, delegations and undelegations
+    pub fn merge(&mut self, other: VestingInfo) {
+        let step_amounts: Vec<&mut Uint128> = match &mut self.mode {
+            VestingMode::Custom { steps } => steps.iter_mut().map(UnlockStep::amount_mut).collect(),
+            VestingMode::Milestones { tranches } => tranches.iter_mut().collect(),
+            VestingMode::Step | VestingMode::Linear => vec![],
+        };
+        let other_step_amounts: Vec<Uint128> = match &other.mode {
+            VestingMode::Custom { steps } => steps.iter().map(UnlockStep::amount).collect(),
+            VestingMode::Milestones { tranches } => tranches.clone(),
+            VestingMode::Step | VestingMode::Linear => vec![],
+        };
+        for (step_amount, other_step_amount) in step_amounts.into_iter().zip(other_step_amounts) {
+            *step_amount += other_step_amount;
+        }
+
+        self.total_amount += other.total_amount;
+        self.claimed_amount += other.claimed_amount;
+        self.vested_amount += other.vested_amount;
+        self.forfeit_pending += other.forfeit_pending;
+        self.amount_per_period = self.total_amount / Uint128::from(self.total_periods);
+        self.last_claimed_period =
+            std::cmp::max(self.last_claimed_period, other.last_claimed_period);
+
+        for other_coin in other.basket {
+            match self
+                .basket
+                .iter_mut()
+                .find(|basket_coin| basket_coin.denom == other_coin.denom)
+            {
+                Some(basket_coin) => {
+                    basket_coin.total_amount += other_coin.total_amount;
+                    basket_coin.claimed_amount += other_coin.claimed_amount;
+                    basket_coin.vested_amount += other_coin.vested_amount;
+                    basket_coin.amount_per_period =
+                        basket_coin.total_amount / Uint128::from(self.total_periods);
+                }
+                None => self.basket.push(other_coin),
+            }
+        }
+        for other_delegation in other.delegations {
+            match self
+                .delegations
+                .iter_mut()
+                .find(|delegation| delegation.validator == other_delegation.validator)
+            {
+                Some(delegation) => delegation.amount += other_delegation.amount,
+                None => self.delegations.push(other_delegation),
+            }
+        }
+        self.unbondings.extend(other.unbondings);
+    }
+}
+
+/// Splits `amount` pro-rata to `weights`, with the rounding remainder going to the last share
+pub fn split_amount(amount: Uint128, weights: &[Uint128]) -> Vec<Uint128> {
+    let total_weight = weights
+        .iter()
+        .fold(Uint128::zero(), |sum, weight| sum + weight);
+    let mut remaining_amount = amount;
+    weights
+        .iter()
+        .enumerate()
+        .map(|(i, weight)| {
+            let share = if i + 1 == weights.len() {
+                remaining_amount
+            } else {
+                amount.multiply_ratio(*weight, total_weight)
+            };
+            remaining_amount -= share;
+            share
+        })
+        .collect()
+}
+
+/// Vesting positions keyed by recipient and vesting id
+pub const VESTING_INFO: Map<(&Addr, u64), VestingInfo> = Map::new("vesting_info");
+
+/// Last vesting id assigned to a position
+pub const VESTING_ID: Item<u64> = Item::new("vesting_id");
+
+/// Recipient of each vesting position keyed by vesting id
+pub const VESTING_RECIPIENT: Map<u64, Addr> = Map::new("vesting_recipient");
+
+/// ## Description
+/// This structure describes the staking rewards accrued per delegated token in a denom.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardIndex {
+    /// Denom of the rewards
+    pub denom: String,
+    /// Rewards accrued per delegated token
+    pub index: Decimal,
+}
+
+/// ## Description
+/// This structure describes the delegations of all vestings to a validator.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct ValidatorInfo {
+    /// Total amount delegated by the vestings
+    pub total_amount: Uint128,
+    /// Staking rewards accrued per delegated token since the first delegation
+    pub reward_indexes: Vec<RewardIndex>,
+}
+
+/// Delegations of all vestings keyed by validator
+pub const VALIDATORS: Map<&str, ValidatorInfo> = Map::new("validators");
+
+/// ## Description
+/// This structure describes the delegation of a vesting to a validator, whose staking rewards are
+/// credited to its recipient as the delegation changes.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DelegatorInfo {
+    /// Delegated amount
+    pub amount: Uint128,
+    /// Reward indexes of the validator when the rewards were last credited
+    pub reward_indexes: Vec<RewardIndex>,
+}
+
+impl DelegatorInfo {
+    /// Returns the staking rewards accrued since the rewards were last credited
+    pub fn pending_rewards(&self, validator_info: &ValidatorInfo) -> Vec<Coin> {
+        validator_info
+            .reward_indexes
+            .iter()
+            .map(|reward_index| {
+                let last_index = self
+                    .reward_indexes
+                    .iter()
+                    .find(|last_index| last_index.denom == reward_index.denom)
+                    .map_or(Decimal::zero(), |last_index| last_index.index);
+                Coin::new(
+                    (self.amount * (reward_index.index - last_index)).u128(),
+                    reward_index.denom.clone(),
+                )
+            })
+            .filter(|coin| !coin.amount.is_zero())
+            .collect()
+    }
+}
+
+/// Delegations of each vesting position keyed by validator and vesting id
+pub const VALIDATOR_DELEGATIONS: Map<(&str, u64), DelegatorInfo> =
+    Map::new("validator_delegations");
+
+/// Staking rewards credited to each recipient and not yet withdrawn
+pub const REWARDS: Map<&Addr, Vec<Coin>> = Map::new("rewards");
+
+/// ## Description
+/// This structure describes the amount of a coin claimed from a vesting position.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClaimedAmount {
+    /// Id of the vesting position
+    pub vesting_id: u64,
+    /// Claimed amount
+    pub amount: Uint128,
+}
+
+/// ## Description
+/// This structure describes a claim paid out over IBC whose transfer may still fail.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IbcPayout {
+    /// Id of the payout
+    pub payout_id: u64,
+    /// The address of the recipient protocol which claimed
+    pub recipient: Addr,
+    /// Channel the coin is transferred over
+    pub channel_id: String,
+    /// Address on the remote chain receiving the coin
+    pub remote_address: String,
+    /// Transferred coin
+    pub amount: Coin,
+    /// Amounts of the coin claimed from each vesting position
+    pub claims: Vec<ClaimedAmount>,
+    /// Block time, in seconds, when the transfer times out
+    pub timeout: u64,
+}
+
+/// IBC payouts keyed by payout id, until they are resolved
+pub const IBC_PAYOUTS: Map<u64, IbcPayout> = Map::new("ibc_payouts");
+
+/// Unresolved IBC payouts keyed by the vesting ids they were claimed from and their payout ids
+pub const IBC_PAYOUT_POSITIONS: Map<(u64, u64), Empty> = Map::new("ibc_payout_positions");
+
+/// Total amounts of the unresolved IBC payouts keyed by denom
+pub const IBC_PAYOUT_TOTALS: Map<&str, Uint128> = Map::new("ibc_payout_totals");
+
+/// Last payout id assigned to a payout
+pub const PAYOUT_ID: Item<u64> = Item::new("payout_id");
+
+/// ## Description
+/// This structure describes a bank payout to a recipient, credited to it if the payout fails.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Payout {
+    /// The address of the recipient protocol paid out
+    pub recipient: Addr,
+    /// Paid out coins
+    pub amount: Vec<Coin>,
+}
+
+/// Last bank payout sent, credited to its recipient if it fails
+pub const PAYOUT: Item<Payout> = Item::new("payout");
+
+/// Failed payouts credited to each recipient until retried
+pub const CREDITS: Map<&Addr, Vec<Coin>> = Map::new("credits");
+
+/// Contracts notified of the claims of each recipient which registered one, along with the funds
+pub const CLAIM_HOOKS: Map<&Addr, Addr> = Map::new("claim_hooks");
+
+/// ## Description
+/// A custom struct for each query response that returns the claim hook of a recipient.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClaimHookResponse {
+    /// Contract notified of the recipient's claims, if registered
+    pub hook: Option<Addr>,
+}
+
+/// ## Description
+/// A custom struct for each query response that returns the failed payouts credited to a recipient.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CreditsResponse {
+    /// Failed payouts to be retried
+    pub credits: Vec<Coin>,
+}
+
+/// ## Description
+/// A custom struct for each query response that returns the unresolved IBC payouts.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IbcPayoutsResponse {
+    /// IBC payouts in order of their ids
+    pub payouts: Vec<IbcPayout>,
+}
+
+/// ## Description
+/// This structure describes the vote of a recipient on a governance proposal, cast by the contract
+/// for its delegations.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VoteRecord {
+    /// The address of the recipient protocol which voted
+    pub voter: Addr,
+    /// Vote option
+    pub vote: VoteOption,
+    /// Amount delegated by the recipient's vestings when the recipient last voted on the proposal
+    pub weight: Uint128,
+}
+
+/// Votes of recipients keyed by proposal id and recipient
+pub const VOTES: Map<(u64, &Addr), VoteRecord> = Map::new("votes");
+
+/// Tallied weights of the recipients' votes by option keyed by proposal id
+pub const VOTE_TALLIES: Map<u64, Vec<(VoteOption, Uint128)>> = Map::new("vote_tallies");
+
+/// ## Description
+/// A custom struct for each query response that returns the votes of recipients on a proposal.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VotesResponse {
+    /// Votes in order of the recipient addresses
+    pub votes: Vec<VoteRecord>,
+}
+
+/// ## Description
+/// A custom struct for each query response that returns the staking rewards credited to a recipient.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardsResponse {
+    /// Rewards credited and not yet withdrawn
+    pub rewards: Vec<Coin>,
+}
+
+/// ## Description
+/// A custom struct for each query response that returns all vesting positions of a recipient.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VestingsResponse {
+    /// Vesting positions in order of their ids
+    pub vestings: Vec<VestingInfo>,
+}
+
+/// ## Description
+/// A custom struct for each query response that returns the CW721 collection of vesting positions.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ContractInfoResponse {
+    /// Name of the collection
+    pub name: String,
+    /// Symbol of the collection
+    pub symbol: String,
+}
+
+/// ## Description
+/// This enum describes when a CW721 approval expires, as encoded by the CW721 interface.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Expiration {
+    /// Expires at the given block height
+    AtHeight(u64),
+    /// Expires at the given block time
+    AtTime(Timestamp),
+    /// Never expires
+    Never {},
+}
+
+/// ## Description
+/// This structure describes an approval to transfer a CW721 token.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Approval {
+    /// Address approved to transfer the token
+    pub spender: String,
+    /// When the approval expires
+    pub expires: Expiration,
+}
+
+/// ## Description
+/// A custom struct for each query response that returns the owner of a vesting position token.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OwnerOfResponse {
+    /// Recipient of the vesting position
+    pub owner: String,
+    /// Approvals to transfer the token, which are not supported
+    pub approvals: Vec<Approval>,
+}
+
+/// ## Description
+/// A custom struct for each query response that returns the information of a vesting position token.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct NftInfoResponse {
+    /// Metadata URI of the token, which is not set
+    pub token_uri: Option<String>,
+    /// The vesting position
+    pub extension: VestingInfo,
+}
+
+/// ## Description
+/// A custom struct for each query response that returns a number of vesting position tokens.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct NumTokensResponse {
+    /// Number of tokens
+    pub count: u64,
+}
+
+/// ## Description
+/// A custom struct for each query response that returns vesting position tokens.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TokensResponse {
+    /// Token ids, i.e. vesting ids, in ascending order
+    pub tokens: Vec<String>,
+}
+
+/// ## Description
+/// This structure describes an unlock in the schedule of a vesting.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ScheduledUnlock {
+    /// Period which must be approved for the amount to unlock
+    pub period: u64,
+    /// Reading of the clock at which the amount unlocks, including any cliff;
+    /// not set for milestones which unlock on approval
+    pub time: Option<u64>,
+    /// Amount unlocked
+    pub amount: Uint128,
+}
+
+/// ## Description
+/// A custom struct for each query response that returns the unlock schedule of a vesting.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ScheduleResponse {
+    /// Unlocks of the vesting in order
+    pub unlocks: Vec<ScheduledUnlock>,
+}
+
+//////////////////////////////////////////////////////////////////////
+// LEGACY
+//////////////////////////////////////////////////////////////////////
+
+/// ## Description
+/// This structure holds the contract parameters of the original release, with a single master.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyConfig {
+    /// Master address who can update tollgate / status of all vestings
+    pub master_address: Addr,
+    /// Specific vesting denom
+    pub denom: String,
+    /// Start time of this vesting contract, i.e. contract init time
+    pub vesting_start_time: u64,
+}
+
+pub const LEGACY_CONFIG: Item<LegacyConfig> = Item::new("config");
+
+/// ## Description
+/// This structure holds the vesting information of each protocol in the original release, which
+/// had a single vesting per recipient.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyVestingInfo {
     /// The address of the recipient protocol to approve the tollgate for
     pub recipient: Addr,
     /// Vesting valid status
@@ -77,4 +1048,5 @@ pub struct VestingInfo {
     pub amount_per_period: Uint128,
 }
 
-pub const VESTING_INFO: Map<&Addr, VestingInfo> = Map::new("vesting_info");
+/// Vestings of the original release keyed by recipient, in the namespace of [`VESTING_INFO`]
+pub const LEGACY_VESTING_INFO: Map<&Addr, LegacyVestingInfo> = Map::new("vesting_info");
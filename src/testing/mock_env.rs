@@ -38,10 +38,20 @@ pub fn mock_init() -> (OwnedDeps<MockStorage, MockApi, MockQuerier>, Response) {
         Vesting {
             recipient: "recipient1".to_string(),
             amount: Uint128::from(300_000_000_001u128),
+            start_time: None,
+            cliff_duration: None,
+            mode: None,
+            tollgate_periods: None,
+            basket: None,
         },
         Vesting {
             recipient: "recipient2".to_string(),
             amount: Uint128::from(300_000_000_000u128),
+            start_time: None,
+            cliff_duration: None,
+            mode: None,
+            tollgate_periods: None,
+            basket: None,
         },
     ];
 
@@ -49,6 +59,13 @@ pub fn mock_init() -> (OwnedDeps<MockStorage, MockApi, MockQuerier>, Response) {
 
     let msg = InstantiateMsg {
         master_address: Some("master_address".to_string()),
+        forfeit_address: None,
+        forfeit_mode: None,
+        acceptance_period: None,
+        claim_expiry_duration: None,
+        clock: None,
+        unbonding_period: None,
+        transferable: None,
         vestings,
     };
 
@@ -66,30 +83,65 @@ pub fn mock_full_init() -> (OwnedDeps<MockStorage, MockApi, MockQuerier>, Respon
         Vesting {
             recipient: "recipient1".to_string(),
             amount: Uint128::from(300_000_000_001u128),
+            start_time: None,
+            cliff_duration: None,
+            mode: None,
+            tollgate_periods: None,
+            basket: None,
         },
         Vesting {
             recipient: "recipient2".to_string(),
             amount: Uint128::from(300_000_000_000u128),
+            start_time: None,
+            cliff_duration: None,
+            mode: None,
+            tollgate_periods: None,
+            basket: None,
         },
         Vesting {
             recipient: "recipient3".to_string(),
             amount: Uint128::from(150_000_000_001u128),
+            start_time: None,
+            cliff_duration: None,
+            mode: None,
+            tollgate_periods: None,
+            basket: None,
         },
         Vesting {
             recipient: "recipient4".to_string(),
             amount: Uint128::from(150_000_000_000u128),
+            start_time: None,
+            cliff_duration: None,
+            mode: None,
+            tollgate_periods: None,
+            basket: None,
         },
         Vesting {
             recipient: "recipient5".to_string(),
             amount: Uint128::from(75_000_000_001u128),
+            start_time: None,
+            cliff_duration: None,
+            mode: None,
+            tollgate_periods: None,
+            basket: None,
         },
         Vesting {
             recipient: "recipient6".to_string(),
             amount: Uint128::from(75_000_000_000u128),
+            start_time: None,
+            cliff_duration: None,
+            mode: None,
+            tollgate_periods: None,
+            basket: None,
         },
         Vesting {
             recipient: "recipient7".to_string(),
             amount: Uint128::from(1u128),
+            start_time: None,
+            cliff_duration: None,
+            mode: None,
+            tollgate_periods: None,
+            basket: None,
         },
     ];
 
@@ -103,6 +155,13 @@ pub fn mock_full_init() -> (OwnedDeps<MockStorage, MockApi, MockQuerier>, Respon
 
     let msg = InstantiateMsg {
         master_address: Some("master_address".to_string()),
+        forfeit_address: None,
+        forfeit_mode: None,
+        acceptance_period: None,
+        claim_expiry_duration: None,
+        clock: None,
+        unbonding_period: None,
+        transferable: None,
         vestings,
     };
 
@@ -1,20 +1,61 @@
 use crate::contract::*;
 use crate::error::ContractError;
 use crate::msg::*;
-use crate::state::{Vesting, VestingInfo};
+use crate::state::{
+    BasketCoin, ClaimHookResponse, ClaimedAmount, Clock, ConfigResponse, ContractInfoResponse,
+    CreditsResponse, Delegation, ForfeitMode, IbcPayout, IbcPayoutsResponse, LegacyConfig,
+    LegacyVestingInfo, NftInfoResponse, NumTokensResponse, OwnerOfResponse, RewardsResponse, Role,
+    RolesResponse, ScheduleResponse, ScheduledUnlock, Stats, TokensResponse, UnlockStep, Vesting,
+    VestingInfo, VestingMode, VestingsResponse, VoteRecord, VotesResponse, CONFIG, LEGACY_CONFIG,
+    LEGACY_VESTING_INFO, VALIDATORS, VESTING_INFO,
+};
 use crate::testing::mock_env::{mock_dependencies, mock_env_time, mock_full_init, mock_init};
-use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::testing::{
+    mock_env, mock_info, MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR,
+};
 use cosmwasm_std::*;
 
 fn query_vesting(deps: Deps, recipient: String) -> VestingInfo {
     let msg = QueryMsg::VestingInfo {
         recipient: recipient.to_string(),
+        vesting_id: None,
     };
     let res = query(deps, mock_env(), msg).unwrap();
     let decoded_res: VestingInfo = from_binary(&res).unwrap();
     decoded_res
 }
 
+// Mirrors the delegations of all vestings as the contract's delegations in the staking module
+fn sync_delegations(
+    deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+    validators: &[Validator],
+) {
+    let mut delegations: Vec<FullDelegation> = vec![];
+    for item in VESTING_INFO.range(&deps.storage, None, None, Order::Ascending) {
+        let (_, vesting_info) = item.unwrap();
+        for delegation in vesting_info.delegations {
+            match delegations
+                .iter_mut()
+                .find(|full_delegation| full_delegation.validator == delegation.validator)
+            {
+                Some(full_delegation) => {
+                    full_delegation.amount.amount += delegation.amount;
+                    full_delegation.can_redelegate.amount += delegation.amount;
+                }
+                None => delegations.push(FullDelegation {
+                    delegator: Addr::unchecked(MOCK_CONTRACT_ADDR),
+                    validator: delegation.validator,
+                    amount: coin(delegation.amount.u128(), "uluna"),
+                    can_redelegate: coin(delegation.amount.u128(), "uluna"),
+                    accumulated_rewards: vec![],
+                }),
+            }
+        }
+    }
+    deps.querier
+        .update_staking("uluna", validators, &delegations);
+}
+
 #[test]
 fn proper_initialization() {
     let (deps, _res) = mock_full_init();
@@ -23,6 +64,7 @@ fn proper_initialization() {
         query_vesting(deps.as_ref(), "recipient1".to_string()),
         VestingInfo {
             recipient: Addr::unchecked("recipient1"),
+            vesting_id: 1,
             active: true,
             approved_periods: 6u64,
             total_periods: 6u64,
@@ -31,6 +73,17 @@ fn proper_initialization() {
             claimed_amount: Uint128::zero(),
             vested_amount: Uint128::from(300000000001u128),
             amount_per_period: Uint128::from(50000000000u128),
+            acceptance_deadline: None,
+            expired: false,
+            start_time: 0u64,
+            cliff_duration: 0u64,
+            mode: VestingMode::Step,
+            tollgate_periods: None,
+            basket: vec![],
+            delegations: vec![],
+            unbondings: vec![],
+            forfeit_pending: Uint128::zero(),
+            redistribution_index: Decimal::zero(),
         }
     );
 
@@ -38,6 +91,7 @@ fn proper_initialization() {
         query_vesting(deps.as_ref(), "recipient2".to_string()),
         VestingInfo {
             recipient: Addr::unchecked("recipient2"),
+            vesting_id: 2,
             active: true,
             approved_periods: 6u64,
             total_periods: 6u64,
@@ -46,6 +100,17 @@ fn proper_initialization() {
             claimed_amount: Uint128::zero(),
             vested_amount: Uint128::from(300000000000u128),
             amount_per_period: Uint128::from(50000000000u128),
+            acceptance_deadline: None,
+            expired: false,
+            start_time: 0u64,
+            cliff_duration: 0u64,
+            mode: VestingMode::Step,
+            tollgate_periods: None,
+            basket: vec![],
+            delegations: vec![],
+            unbondings: vec![],
+            forfeit_pending: Uint128::zero(),
+            redistribution_index: Decimal::zero(),
         }
     );
 
@@ -53,6 +118,7 @@ fn proper_initialization() {
         query_vesting(deps.as_ref(), "recipient3".to_string()),
         VestingInfo {
             recipient: Addr::unchecked("recipient3"),
+            vesting_id: 3,
             active: true,
             approved_periods: 6u64,
             total_periods: 6u64,
@@ -61,6 +127,17 @@ fn proper_initialization() {
             claimed_amount: Uint128::zero(),
             vested_amount: Uint128::from(150000000001u128),
             amount_per_period: Uint128::from(25000000000u128),
+            acceptance_deadline: None,
+            expired: false,
+            start_time: 0u64,
+            cliff_duration: 0u64,
+            mode: VestingMode::Step,
+            tollgate_periods: None,
+            basket: vec![],
+            delegations: vec![],
+            unbondings: vec![],
+            forfeit_pending: Uint128::zero(),
+            redistribution_index: Decimal::zero(),
         }
     );
 
@@ -68,6 +145,7 @@ fn proper_initialization() {
         query_vesting(deps.as_ref(), "recipient4".to_string()),
         VestingInfo {
             recipient: Addr::unchecked("recipient4"),
+            vesting_id: 4,
             active: true,
             approved_periods: 6u64,
             total_periods: 6u64,
@@ -76,6 +154,17 @@ fn proper_initialization() {
             claimed_amount: Uint128::zero(),
             vested_amount: Uint128::from(150000000000u128),
             amount_per_period: Uint128::from(25000000000u128),
+            acceptance_deadline: None,
+            expired: false,
+            start_time: 0u64,
+            cliff_duration: 0u64,
+            mode: VestingMode::Step,
+            tollgate_periods: None,
+            basket: vec![],
+            delegations: vec![],
+            unbondings: vec![],
+            forfeit_pending: Uint128::zero(),
+            redistribution_index: Decimal::zero(),
         }
     );
 
@@ -83,6 +172,7 @@ fn proper_initialization() {
         query_vesting(deps.as_ref(), "recipient5".to_string()),
         VestingInfo {
             recipient: Addr::unchecked("recipient5"),
+            vesting_id: 5,
             active: true,
             approved_periods: 6u64,
             total_periods: 6u64,
@@ -91,6 +181,17 @@ fn proper_initialization() {
             claimed_amount: Uint128::zero(),
             vested_amount: Uint128::from(75000000001u128),
             amount_per_period: Uint128::from(12500000000u128),
+            acceptance_deadline: None,
+            expired: false,
+            start_time: 0u64,
+            cliff_duration: 0u64,
+            mode: VestingMode::Step,
+            tollgate_periods: None,
+            basket: vec![],
+            delegations: vec![],
+            unbondings: vec![],
+            forfeit_pending: Uint128::zero(),
+            redistribution_index: Decimal::zero(),
         }
     );
 
@@ -98,6 +199,7 @@ fn proper_initialization() {
         query_vesting(deps.as_ref(), "recipient6".to_string()),
         VestingInfo {
             recipient: Addr::unchecked("recipient6"),
+            vesting_id: 6,
             active: true,
             approved_periods: 6u64,
             total_periods: 6u64,
@@ -106,6 +208,17 @@ fn proper_initialization() {
             claimed_amount: Uint128::zero(),
             vested_amount: Uint128::from(75000000000u128),
             amount_per_period: Uint128::from(12500000000u128),
+            acceptance_deadline: None,
+            expired: false,
+            start_time: 0u64,
+            cliff_duration: 0u64,
+            mode: VestingMode::Step,
+            tollgate_periods: None,
+            basket: vec![],
+            delegations: vec![],
+            unbondings: vec![],
+            forfeit_pending: Uint128::zero(),
+            redistribution_index: Decimal::zero(),
         }
     );
 
@@ -113,6 +226,7 @@ fn proper_initialization() {
         query_vesting(deps.as_ref(), "recipient7".to_string()),
         VestingInfo {
             recipient: Addr::unchecked("recipient7"),
+            vesting_id: 7,
             active: true,
             approved_periods: 6u64,
             total_periods: 3u64,
@@ -121,6 +235,17 @@ fn proper_initialization() {
             claimed_amount: Uint128::zero(),
             vested_amount: Uint128::from(1u128),
             amount_per_period: Uint128::from(0u128),
+            acceptance_deadline: None,
+            expired: false,
+            start_time: 0u64,
+            cliff_duration: 0u64,
+            mode: VestingMode::Step,
+            tollgate_periods: None,
+            basket: vec![],
+            delegations: vec![],
+            unbondings: vec![],
+            forfeit_pending: Uint128::zero(),
+            redistribution_index: Decimal::zero(),
         }
     );
 }
@@ -133,15 +258,32 @@ pub fn test_fail_init() {
         Vesting {
             recipient: "recipient1".to_string(),
             amount: Uint128::from(300_000_000_001u128),
+            start_time: None,
+            cliff_duration: None,
+            mode: None,
+            tollgate_periods: None,
+            basket: None,
         },
         Vesting {
             recipient: "recipient2".to_string(),
             amount: Uint128::from(300_000_000_000u128),
+            start_time: None,
+            cliff_duration: None,
+            mode: None,
+            tollgate_periods: None,
+            basket: None,
         },
     ];
 
     let msg = InstantiateMsg {
         master_address: Some("master_address".to_string()),
+        forfeit_address: None,
+        forfeit_mode: None,
+        acceptance_period: None,
+        claim_expiry_duration: None,
+        clock: None,
+        unbonding_period: None,
+        transferable: None,
         vestings,
     };
 
@@ -153,39 +295,36 @@ pub fn test_fail_init() {
     let res = instantiate(deps.as_mut(), mock_env_time(0), info, msg).unwrap_err();
     assert_eq!(res, ContractError::MismatchedAssetType {});
 
-    let vestings = vec![
-        Vesting {
-            recipient: "recipient3".to_string(),
-            amount: Uint128::from(300_000_000_001u128),
-        },
-        Vesting {
-            recipient: "recipient3".to_string(),
-            amount: Uint128::from(300_000_000_000u128),
-        },
-    ];
-
-    let msg = InstantiateMsg {
-        master_address: Some("master_address".to_string()),
-        vestings,
-    };
-
-    let info = mock_info("addr0000", &[coin(600_000_000_001u128, "uluna")]);
-    let res = instantiate(deps.as_mut(), mock_env_time(0), info, msg).unwrap_err();
-    assert_eq!(res, ContractError::DuplicatedRecipient {});
-
     let vestings = vec![
         Vesting {
             recipient: "recipient4".to_string(),
             amount: Uint128::from(300_000_000_001u128),
+            start_time: None,
+            cliff_duration: None,
+            mode: None,
+            tollgate_periods: None,
+            basket: None,
         },
         Vesting {
             recipient: "recipient5".to_string(),
             amount: Uint128::from(0u128),
+            start_time: None,
+            cliff_duration: None,
+            mode: None,
+            tollgate_periods: None,
+            basket: None,
         },
     ];
 
     let msg = InstantiateMsg {
         master_address: Some("master_address".to_string()),
+        forfeit_address: None,
+        forfeit_mode: None,
+        acceptance_period: None,
+        claim_expiry_duration: None,
+        clock: None,
+        unbonding_period: None,
+        transferable: None,
         vestings,
     };
 
@@ -207,7 +346,9 @@ fn test_approve_tollgate_and_claim() {
     let info = mock_info("master_address", &[]);
     let msg = ExecuteMsg::ApproveTollgate {
         recipient: "recipient1".to_string(),
+        vesting_id: None,
         approve: true,
+        payout: None,
     };
     let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
     assert_eq!(res, ContractError::NextTollgateTimeNotReached {});
@@ -216,7 +357,9 @@ fn test_approve_tollgate_and_claim() {
     let info = mock_info("master_address", &[]);
     let msg = ExecuteMsg::ApproveTollgate {
         recipient: "recipient1".to_string(),
+        vesting_id: None,
         approve: true,
+        payout: None,
     };
     execute(deps.as_mut(), env, info, msg).unwrap();
 
@@ -224,6 +367,7 @@ fn test_approve_tollgate_and_claim() {
         query_vesting(deps.as_ref(), "recipient1".to_string()),
         VestingInfo {
             recipient: Addr::unchecked("recipient1"),
+            vesting_id: 1,
             active: true,
             approved_periods: 6u64,
             total_periods: 12u64,
@@ -232,25 +376,40 @@ fn test_approve_tollgate_and_claim() {
             claimed_amount: Uint128::zero(),
             vested_amount: Uint128::from(300000000001u128),
             amount_per_period: Uint128::from(25000000000u128),
+            acceptance_deadline: None,
+            expired: false,
+            start_time: 0u64,
+            cliff_duration: 0u64,
+            mode: VestingMode::Step,
+            tollgate_periods: None,
+            basket: vec![],
+            delegations: vec![],
+            unbondings: vec![],
+            forfeit_pending: Uint128::zero(),
+            redistribution_index: Decimal::zero(),
         }
     );
 
     let env = mock_env_time(SECONDS_PER_PERIOD * 3 + 10);
     let info = mock_info("recipient1", &[]);
-    let msg = ExecuteMsg::Claim {};
+    let msg = ExecuteMsg::Claim { vesting_ids: None, ibc: None };
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-            to_address: "recipient1".to_string(),
-            amount: coins(75000000000u128, "uluna"),
-        }))],
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient1".to_string(),
+                amount: coins(75000000000u128, "uluna"),
+            }),
+            0u64,
+        )],
     );
 
     assert_eq!(
         query_vesting(deps.as_ref(), "recipient1".to_string()),
         VestingInfo {
             recipient: Addr::unchecked("recipient1"),
+            vesting_id: 1,
             active: true,
             approved_periods: 6u64,
             total_periods: 12u64,
@@ -259,6 +418,17 @@ fn test_approve_tollgate_and_claim() {
             claimed_amount: Uint128::from(75000000000u128),
             vested_amount: Uint128::from(225000000001u128),
             amount_per_period: Uint128::from(25000000000u128),
+            acceptance_deadline: None,
+            expired: false,
+            start_time: 0u64,
+            cliff_duration: 0u64,
+            mode: VestingMode::Step,
+            tollgate_periods: None,
+            basket: vec![],
+            delegations: vec![],
+            unbondings: vec![],
+            forfeit_pending: Uint128::zero(),
+            redistribution_index: Decimal::zero(),
         }
     );
 
@@ -266,7 +436,9 @@ fn test_approve_tollgate_and_claim() {
     let info = mock_info("master_address", &[]);
     let msg = ExecuteMsg::ApproveTollgate {
         recipient: "recipient1".to_string(),
+        vesting_id: None,
         approve: true,
+        payout: None,
     };
     execute(deps.as_mut(), env, info, msg).unwrap();
 
@@ -274,6 +446,7 @@ fn test_approve_tollgate_and_claim() {
         query_vesting(deps.as_ref(), "recipient1".to_string()),
         VestingInfo {
             recipient: Addr::unchecked("recipient1"),
+            vesting_id: 1,
             active: true,
             approved_periods: 9u64,
             total_periods: 12u64,
@@ -282,6 +455,17 @@ fn test_approve_tollgate_and_claim() {
             claimed_amount: Uint128::from(75000000000u128),
             vested_amount: Uint128::from(225000000001u128),
             amount_per_period: Uint128::from(25000000000u128),
+            acceptance_deadline: None,
+            expired: false,
+            start_time: 0u64,
+            cliff_duration: 0u64,
+            mode: VestingMode::Step,
+            tollgate_periods: None,
+            basket: vec![],
+            delegations: vec![],
+            unbondings: vec![],
+            forfeit_pending: Uint128::zero(),
+            redistribution_index: Decimal::zero(),
         }
     );
 
@@ -289,13 +473,16 @@ fn test_approve_tollgate_and_claim() {
     let info = mock_info("master_address", &[]);
     let msg = ExecuteMsg::ApproveTollgate {
         recipient: "recipient1".to_string(),
+        vesting_id: None,
         approve: true,
+        payout: None,
     };
     execute(deps.as_mut(), env, info, msg).unwrap();
     assert_eq!(
         query_vesting(deps.as_ref(), "recipient1".to_string()),
         VestingInfo {
             recipient: Addr::unchecked("recipient1"),
+            vesting_id: 1,
             active: true,
             approved_periods: 12u64,
             total_periods: 12u64,
@@ -304,6 +491,17 @@ fn test_approve_tollgate_and_claim() {
             claimed_amount: Uint128::from(75000000000u128),
             vested_amount: Uint128::from(225000000001u128),
             amount_per_period: Uint128::from(25000000000u128),
+            acceptance_deadline: None,
+            expired: false,
+            start_time: 0u64,
+            cliff_duration: 0u64,
+            mode: VestingMode::Step,
+            tollgate_periods: None,
+            basket: vec![],
+            delegations: vec![],
+            unbondings: vec![],
+            forfeit_pending: Uint128::zero(),
+            redistribution_index: Decimal::zero(),
         }
     );
 
@@ -311,26 +509,32 @@ fn test_approve_tollgate_and_claim() {
     let info = mock_info("master_address", &[]);
     let msg = ExecuteMsg::ApproveTollgate {
         recipient: "recipient1".to_string(),
+        vesting_id: None,
         approve: true,
+        payout: None,
     };
     let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
     assert_eq!(res, ContractError::NoTollgateRequired {});
 
     let env = mock_env_time(SECONDS_PER_PERIOD * 12);
     let info = mock_info("recipient1", &[]);
-    let msg = ExecuteMsg::Claim {};
+    let msg = ExecuteMsg::Claim { vesting_ids: None, ibc: None };
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-            to_address: "recipient1".to_string(),
-            amount: coins(225000000000u128, "uluna"),
-        }))],
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient1".to_string(),
+                amount: coins(225000000000u128, "uluna"),
+            }),
+            0u64,
+        )],
     );
     assert_eq!(
         query_vesting(deps.as_ref(), "recipient1".to_string()),
         VestingInfo {
             recipient: Addr::unchecked("recipient1"),
+            vesting_id: 1,
             active: true,
             approved_periods: 12u64,
             total_periods: 12u64,
@@ -339,6 +543,17 @@ fn test_approve_tollgate_and_claim() {
             claimed_amount: Uint128::from(300000000000u128),
             vested_amount: Uint128::from(1u128),
             amount_per_period: Uint128::from(25000000000u128),
+            acceptance_deadline: None,
+            expired: false,
+            start_time: 0u64,
+            cliff_duration: 0u64,
+            mode: VestingMode::Step,
+            tollgate_periods: None,
+            basket: vec![],
+            delegations: vec![],
+            unbondings: vec![],
+            forfeit_pending: Uint128::zero(),
+            redistribution_index: Decimal::zero(),
         }
     );
 } */
@@ -351,25 +566,31 @@ fn test_disapprove_tollgate_and_claim() {
     let info = mock_info("master_address", &[]);
     let msg = ExecuteMsg::ApproveTollgate {
         recipient: "recipient1".to_string(),
+        vesting_id: None,
         approve: true,
+        payout: None,
     };
     execute(deps.as_mut(), env, info, msg).unwrap();
 
     let env = mock_env_time(SECONDS_PER_PERIOD * 3 + 1);
     let info = mock_info("recipient1", &[]);
-    let msg = ExecuteMsg::Claim {};
+    let msg = ExecuteMsg::Claim { vesting_ids: None, ibc: None };
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-            to_address: "recipient1".to_string(),
-            amount: coins(75000000000u128, "uluna"),
-        }))],
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient1".to_string(),
+                amount: coins(75000000000u128, "uluna"),
+            }),
+            0u64,
+        )],
     );
     assert_eq!(
         query_vesting(deps.as_ref(), "recipient1".to_string()),
         VestingInfo {
             recipient: Addr::unchecked("recipient1"),
+            vesting_id: 1,
             active: true,
             approved_periods: 6u64,
             total_periods: 12u64,
@@ -378,6 +599,17 @@ fn test_disapprove_tollgate_and_claim() {
             claimed_amount: Uint128::from(75000000000u128),
             vested_amount: Uint128::from(225000000001u128),
             amount_per_period: Uint128::from(25000000000u128),
+            acceptance_deadline: None,
+            expired: false,
+            start_time: 0u64,
+            cliff_duration: 0u64,
+            mode: VestingMode::Step,
+            tollgate_periods: None,
+            basket: vec![],
+            delegations: vec![],
+            unbondings: vec![],
+            forfeit_pending: Uint128::zero(),
+            redistribution_index: Decimal::zero(),
         }
     );
 
@@ -385,7 +617,9 @@ fn test_disapprove_tollgate_and_claim() {
     let info = mock_info("master_address", &[]);
     let msg = ExecuteMsg::ApproveTollgate {
         recipient: "recipient1".to_string(),
+        vesting_id: None,
         approve: true,
+        payout: None,
     };
     execute(deps.as_mut(), env, info, msg).unwrap();
 
@@ -393,7 +627,9 @@ fn test_disapprove_tollgate_and_claim() {
     let info = mock_info("master_address", &[]);
     let msg = ExecuteMsg::ApproveTollgate {
         recipient: "recipient1".to_string(),
+        vesting_id: None,
         approve: false,
+        payout: None,
     };
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
     assert_eq!(
@@ -407,6 +643,7 @@ fn test_disapprove_tollgate_and_claim() {
         query_vesting(deps.as_ref(), "recipient1".to_string()),
         VestingInfo {
             recipient: Addr::unchecked("recipient1"),
+            vesting_id: 1,
             active: false,
             approved_periods: 9u64,
             total_periods: 12u64,
@@ -415,24 +652,39 @@ fn test_disapprove_tollgate_and_claim() {
             claimed_amount: Uint128::from(75000000000u128),
             vested_amount: Uint128::from(150000000000u128),
             amount_per_period: Uint128::from(25000000000u128),
+            acceptance_deadline: None,
+            expired: false,
+            start_time: 0u64,
+            cliff_duration: 0u64,
+            mode: VestingMode::Step,
+            tollgate_periods: None,
+            basket: vec![],
+            delegations: vec![],
+            unbondings: vec![],
+            forfeit_pending: Uint128::zero(),
+            redistribution_index: Decimal::zero(),
         }
     );
 
     let env = mock_env_time(SECONDS_PER_PERIOD * 12);
     let info = mock_info("recipient1", &[]);
-    let msg = ExecuteMsg::Claim {};
+    let msg = ExecuteMsg::Claim { vesting_ids: None, ibc: None };
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-            to_address: "recipient1".to_string(),
-            amount: coins(150000000000u128, "uluna"),
-        }))],
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient1".to_string(),
+                amount: coins(150000000000u128, "uluna"),
+            }),
+            0u64,
+        )],
     );
     assert_eq!(
         query_vesting(deps.as_ref(), "recipient1".to_string()),
         VestingInfo {
             recipient: Addr::unchecked("recipient1"),
+            vesting_id: 1,
             active: false,
             approved_periods: 9u64,
             total_periods: 12u64,
@@ -441,6 +693,17 @@ fn test_disapprove_tollgate_and_claim() {
             claimed_amount: Uint128::from(225000000000u128),
             vested_amount: Uint128::zero(),
             amount_per_period: Uint128::from(25000000000u128),
+            acceptance_deadline: None,
+            expired: false,
+            start_time: 0u64,
+            cliff_duration: 0u64,
+            mode: VestingMode::Step,
+            tollgate_periods: None,
+            basket: vec![],
+            delegations: vec![],
+            unbondings: vec![],
+            forfeit_pending: Uint128::zero(),
+            redistribution_index: Decimal::zero(),
         }
     );
 }
@@ -451,19 +714,26 @@ fn test_first_period_claim() {
 
     let env = mock_env_time(SECONDS_PER_PERIOD * 2);
     let info = mock_info("recipient1", &[]);
-    let msg = ExecuteMsg::Claim {};
+    let msg = ExecuteMsg::Claim {
+        vesting_ids: None,
+        ibc: None,
+    };
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-            to_address: "recipient1".to_string(),
-            amount: coins(100000000000u128, "uluna"),
-        }))],
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient1".to_string(),
+                amount: coins(100000000000u128, "uluna"),
+            }),
+            0u64
+        )],
     );
     assert_eq!(
         query_vesting(deps.as_ref(), "recipient1".to_string()),
         VestingInfo {
             recipient: Addr::unchecked("recipient1"),
+            vesting_id: 1,
             active: true,
             approved_periods: 6u64,
             total_periods: 6u64,
@@ -472,24 +742,42 @@ fn test_first_period_claim() {
             claimed_amount: Uint128::from(100000000000u128),
             vested_amount: Uint128::from(200000000001u128),
             amount_per_period: Uint128::from(50000000000u128),
+            acceptance_deadline: None,
+            expired: false,
+            start_time: 0u64,
+            cliff_duration: 0u64,
+            mode: VestingMode::Step,
+            tollgate_periods: None,
+            basket: vec![],
+            delegations: vec![],
+            unbondings: vec![],
+            forfeit_pending: Uint128::zero(),
+            redistribution_index: Decimal::zero(),
         }
     );
 
     let env = mock_env_time(SECONDS_PER_PERIOD * 3);
     let info = mock_info("recipient1", &[]);
-    let msg = ExecuteMsg::Claim {};
+    let msg = ExecuteMsg::Claim {
+        vesting_ids: None,
+        ibc: None,
+    };
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-            to_address: "recipient1".to_string(),
-            amount: coins(50000000000u128, "uluna"),
-        }))],
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient1".to_string(),
+                amount: coins(50000000000u128, "uluna"),
+            }),
+            0u64
+        )],
     );
     assert_eq!(
         query_vesting(deps.as_ref(), "recipient1".to_string()),
         VestingInfo {
             recipient: Addr::unchecked("recipient1"),
+            vesting_id: 1,
             active: true,
             approved_periods: 6u64,
             total_periods: 6u64,
@@ -498,6 +786,17 @@ fn test_first_period_claim() {
             claimed_amount: Uint128::from(150000000000u128),
             vested_amount: Uint128::from(150000000001u128),
             amount_per_period: Uint128::from(50000000000u128),
+            acceptance_deadline: None,
+            expired: false,
+            start_time: 0u64,
+            cliff_duration: 0u64,
+            mode: VestingMode::Step,
+            tollgate_periods: None,
+            basket: vec![],
+            delegations: vec![],
+            unbondings: vec![],
+            forfeit_pending: Uint128::zero(),
+            redistribution_index: Decimal::zero(),
         }
     );
 }
@@ -510,19 +809,25 @@ fn test_spam_approve() {
     let info = mock_info("master_address", &[]);
     let msg = ExecuteMsg::ApproveTollgate {
         recipient: "recipient1".to_string(),
+        vesting_id: None,
         approve: true,
+        payout: None,
     };
     execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
     let msg = ExecuteMsg::ApproveTollgate {
         recipient: "recipient1".to_string(),
+        vesting_id: None,
         approve: false,
+        payout: None,
     };
     execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
     let msg = ExecuteMsg::ApproveTollgate {
         recipient: "recipient1".to_string(),
+        vesting_id: None,
         approve: true,
+        payout: None,
     };
     let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
 
@@ -535,19 +840,26 @@ fn test_no_claimable() {
 
     let env = mock_env_time(SECONDS_PER_PERIOD * 2);
     let info = mock_info("recipient1", &[]);
-    let msg = ExecuteMsg::Claim {};
+    let msg = ExecuteMsg::Claim {
+        vesting_ids: None,
+        ibc: None,
+    };
     let res = execute(deps.as_mut(), env, info.clone(), msg.clone()).unwrap();
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
-            to_address: "recipient1".to_string(),
-            amount: coins(100000000000u128, "uluna"),
-        }))],
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient1".to_string(),
+                amount: coins(100000000000u128, "uluna"),
+            }),
+            0u64
+        )],
     );
     assert_eq!(
         query_vesting(deps.as_ref(), "recipient1".to_string()),
         VestingInfo {
             recipient: Addr::unchecked("recipient1"),
+            vesting_id: 1,
             active: true,
             approved_periods: 6u64,
             total_periods: 6u64,
@@ -556,11 +868,3834 @@ fn test_no_claimable() {
             claimed_amount: Uint128::from(100000000000u128),
             vested_amount: Uint128::from(200000000001u128),
             amount_per_period: Uint128::from(50000000000u128),
+            acceptance_deadline: None,
+            expired: false,
+            start_time: 0u64,
+            cliff_duration: 0u64,
+            mode: VestingMode::Step,
+            tollgate_periods: None,
+            basket: vec![],
+            delegations: vec![],
+            unbondings: vec![],
+            forfeit_pending: Uint128::zero(),
+            redistribution_index: Decimal::zero(),
         }
     );
 
     let env = mock_env_time(SECONDS_PER_PERIOD * 2 + 5);
-    let msg = ExecuteMsg::Claim {};
+    let msg = ExecuteMsg::Claim {
+        vesting_ids: None,
+        ibc: None,
+    };
     let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
     assert_eq!(res, ContractError::NoClaimable {});
 }
+
+#[test]
+fn test_grant_and_revoke_roles() {
+    let (mut deps, _) = mock_init();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Roles {}).unwrap();
+    let roles: RolesResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        roles,
+        RolesResponse {
+            admins: vec!["master_address".to_string()],
+            reviewers: vec!["master_address".to_string()],
+            guardians: vec!["master_address".to_string()],
+            funders: vec!["master_address".to_string()],
+        }
+    );
+
+    // Only admins can grant roles
+    let info = mock_info("reviewer", &[]);
+    let msg = ExecuteMsg::GrantRole {
+        role: Role::Reviewer,
+        address: "reviewer".to_string(),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    let info = mock_info("master_address", &[]);
+    execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(
+        res,
+        ContractError::RoleAlreadyGranted {
+            address: "reviewer".to_string()
+        }
+    );
+
+    let msg = ExecuteMsg::RevokeRole {
+        role: Role::Reviewer,
+        address: "master_address".to_string(),
+    };
+    execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(
+        res,
+        ContractError::RoleNotGranted {
+            address: "master_address".to_string()
+        }
+    );
+
+    // Tollgates can only be decided by reviewers
+    let msg = ExecuteMsg::ApproveTollgate {
+        recipient: "recipient1".to_string(),
+        vesting_id: None,
+        approve: true,
+        payout: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+    let res = execute(deps.as_mut(), mock_env(), mock_info("reviewer", &[]), msg).unwrap_err();
+    assert_eq!(res, ContractError::NoTollgateRequired {});
+
+    // The last admin cannot be revoked
+    let msg = ExecuteMsg::RevokeRole {
+        role: Role::Admin,
+        address: "master_address".to_string(),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(res, ContractError::LastAdmin {});
+}
+
+#[test]
+fn test_add_and_top_up_vestings() {
+    let (mut deps, _) = mock_init();
+
+    // Only funders can add vestings
+    let msg = ExecuteMsg::AddVestings {
+        vestings: vec![Vesting {
+            recipient: "recipient3".to_string(),
+            amount: Uint128::from(30_000_000_000u128),
+            start_time: None,
+            cliff_duration: None,
+            mode: None,
+            tollgate_periods: None,
+            basket: None,
+        }],
+    };
+    let info = mock_info("recipient1", &[coin(30_000_000_000u128, "uluna")]);
+    let res = execute(deps.as_mut(), mock_env_time(0), info, msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    let info = mock_info("master_address", &[coin(30_000_000_000u128, "uluna")]);
+    execute(deps.as_mut(), mock_env_time(0), info, msg).unwrap();
+    assert_eq!(
+        query_vesting(deps.as_ref(), "recipient3".to_string()),
+        VestingInfo {
+            recipient: Addr::unchecked("recipient3"),
+            vesting_id: 3,
+            active: true,
+            approved_periods: 6u64,
+            total_periods: 3u64,
+            last_claimed_period: 0u64,
+            total_amount: Uint128::from(30000000000u128),
+            claimed_amount: Uint128::zero(),
+            vested_amount: Uint128::from(30000000000u128),
+            amount_per_period: Uint128::from(10000000000u128),
+            acceptance_deadline: None,
+            expired: false,
+            start_time: 0u64,
+            cliff_duration: 0u64,
+            mode: VestingMode::Step,
+            tollgate_periods: None,
+            basket: vec![],
+            delegations: vec![],
+            unbondings: vec![],
+            forfeit_pending: Uint128::zero(),
+            redistribution_index: Decimal::zero(),
+        }
+    );
+
+    let env = mock_env_time(SECONDS_PER_PERIOD * 2);
+    let msg = ExecuteMsg::Claim {
+        vesting_ids: None,
+        ibc: None,
+    };
+    execute(deps.as_mut(), env, mock_info("recipient1", &[]), msg).unwrap();
+
+    // Top up is spread across all periods, including the claimed ones
+    let msg = ExecuteMsg::TopUpVesting {
+        recipient: "recipient1".to_string(),
+        vesting_id: None,
+    };
+    let env = mock_env_time(SECONDS_PER_PERIOD * 2);
+    let info = mock_info("master_address", &[coin(60_000_000_000u128, "uluna")]);
+    execute(deps.as_mut(), env, info, msg).unwrap();
+
+    let env = mock_env_time(SECONDS_PER_PERIOD * 3);
+    let msg = ExecuteMsg::Claim {
+        vesting_ids: None,
+        ibc: None,
+    };
+    let res = execute(deps.as_mut(), env, mock_info("recipient1", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient1".to_string(),
+                amount: coins(80000000000u128, "uluna"),
+            }),
+            0u64
+        )],
+    );
+    assert_eq!(
+        query_vesting(deps.as_ref(), "recipient1".to_string()),
+        VestingInfo {
+            recipient: Addr::unchecked("recipient1"),
+            vesting_id: 1,
+            active: true,
+            approved_periods: 6u64,
+            total_periods: 6u64,
+            last_claimed_period: 3u64,
+            total_amount: Uint128::from(360000000001u128),
+            claimed_amount: Uint128::from(180000000000u128),
+            vested_amount: Uint128::from(180000000001u128),
+            amount_per_period: Uint128::from(60000000000u128),
+            acceptance_deadline: None,
+            expired: false,
+            start_time: 0u64,
+            cliff_duration: 0u64,
+            mode: VestingMode::Step,
+            tollgate_periods: None,
+            basket: vec![],
+            delegations: vec![],
+            unbondings: vec![],
+            forfeit_pending: Uint128::zero(),
+            redistribution_index: Decimal::zero(),
+        }
+    );
+}
+
+#[test]
+fn test_pause_and_unpause() {
+    let (mut deps, _) = mock_init();
+
+    // Only guardians can pause
+    let msg = ExecuteMsg::Pause {
+        recipient: None,
+        auto_unpause_time: Some(SECONDS_PER_PERIOD * 3),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env_time(0),
+        mock_info("recipient1", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    let info = mock_info("master_address", &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env_time(SECONDS_PER_PERIOD * 3),
+        info.clone(),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::InvalidAutoUnpauseTime {});
+    execute(deps.as_mut(), mock_env_time(0), info.clone(), msg).unwrap();
+
+    let env = mock_env_time(SECONDS_PER_PERIOD * 2);
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert!(config.paused);
+    assert_eq!(config.auto_unpause_time, Some(SECONDS_PER_PERIOD * 3));
+
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("recipient1", &[]),
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Paused {});
+
+    // The pause is automatically lifted at `auto_unpause_time`
+    let env = mock_env_time(SECONDS_PER_PERIOD * 3);
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert!(!config.paused);
+    execute(
+        deps.as_mut(),
+        env,
+        mock_info("recipient1", &[]),
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap();
+
+    // Freezing a recipient does not affect the others
+    let msg = ExecuteMsg::Pause {
+        recipient: Some("recipient1".to_string()),
+        auto_unpause_time: None,
+    };
+    execute(deps.as_mut(), mock_env_time(0), info.clone(), msg).unwrap();
+
+    let env = mock_env_time(SECONDS_PER_PERIOD * 4);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient1", &[]),
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Paused {});
+
+    // Nor can the frozen recipient's vestings be expired, reclaimed or settled
+    for msg in [
+        ExecuteMsg::ExpireVesting {
+            recipient: "recipient1".to_string(),
+            vesting_id: None,
+        },
+        ExecuteMsg::ReclaimUnaccepted {
+            recipient: "recipient1".to_string(),
+            vesting_id: None,
+        },
+        ExecuteMsg::SettleForfeit {
+            recipient: "recipient1".to_string(),
+            vesting_id: None,
+        },
+    ] {
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert_eq!(res, ContractError::Paused {});
+    }
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient2", &[]),
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::Unpause {
+        recipient: Some("recipient1".to_string()),
+    };
+    execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(res, ContractError::NotPaused {});
+    execute(
+        deps.as_mut(),
+        env,
+        mock_info("recipient1", &[]),
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_update_config() {
+    let (mut deps, _) = mock_init();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(config.forfeit_address, "master_address".to_string());
+    assert_eq!(config.forfeit_mode, ForfeitMode::Transfer);
+
+    // Only admins can update the config
+    let msg = ExecuteMsg::UpdateConfig {
+        forfeit_address: Some("community_pool".to_string()),
+        forfeit_mode: Some(ForfeitMode::Redistribute),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("recipient1", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("master_address", &[]),
+        msg,
+    )
+    .unwrap();
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(config.forfeit_address, "community_pool".to_string());
+    assert_eq!(config.forfeit_mode, ForfeitMode::Redistribute);
+
+    let msg = ExecuteMsg::UpdateConfig {
+        forfeit_address: None,
+        forfeit_mode: Some(ForfeitMode::Burn),
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("master_address", &[]),
+        msg,
+    )
+    .unwrap();
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(config.forfeit_address, "community_pool".to_string());
+    assert_eq!(config.forfeit_mode, ForfeitMode::Burn);
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Stats {}).unwrap();
+    let stats: Stats = from_binary(&res).unwrap();
+    assert_eq!(stats, Stats::default());
+}
+
+#[test]
+fn test_renounce() {
+    let (mut deps, _) = mock_init();
+
+    let env = mock_env_time(SECONDS_PER_PERIOD * 2);
+    let info = mock_info("recipient1", &[]);
+    let msg = ExecuteMsg::Renounce {
+        vesting_id: None,
+        keep_claimable: true,
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::reply_on_error(
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "recipient1".to_string(),
+                    amount: coins(100000000000u128, "uluna"),
+                }),
+                0u64
+            ),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "master_address".to_string(),
+                amount: coins(200000000001u128, "uluna"),
+            }))
+        ],
+    );
+    assert_eq!(
+        query_vesting(deps.as_ref(), "recipient1".to_string()),
+        VestingInfo {
+            recipient: Addr::unchecked("recipient1"),
+            vesting_id: 1,
+            active: false,
+            approved_periods: 6u64,
+            total_periods: 6u64,
+            last_claimed_period: 2u64,
+            total_amount: Uint128::from(300000000001u128),
+            claimed_amount: Uint128::from(100000000000u128),
+            vested_amount: Uint128::zero(),
+            amount_per_period: Uint128::from(50000000000u128),
+            acceptance_deadline: None,
+            expired: false,
+            start_time: 0u64,
+            cliff_duration: 0u64,
+            mode: VestingMode::Step,
+            tollgate_periods: None,
+            basket: vec![],
+            delegations: vec![],
+            unbondings: vec![],
+            forfeit_pending: Uint128::zero(),
+            redistribution_index: Decimal::zero(),
+        }
+    );
+
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(res, ContractError::VestingNotActive {});
+
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("recipient1", &[]),
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::NoClaimable {});
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Stats {}).unwrap();
+    let stats: Stats = from_binary(&res).unwrap();
+    assert_eq!(
+        stats,
+        Stats {
+            total_forfeited: Uint128::from(200000000001u128),
+            total_burned: Uint128::zero(),
+            total_expired: Uint128::zero(),
+        }
+    );
+}
+
+#[test]
+fn test_renounce_redistribute_and_burn() {
+    let (mut deps, _) = mock_full_init();
+
+    let msg = ExecuteMsg::UpdateConfig {
+        forfeit_address: None,
+        forfeit_mode: Some(ForfeitMode::Redistribute),
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("master_address", &[]),
+        msg,
+    )
+    .unwrap();
+
+    // Without keeping the claimable periods, the whole remainder is forfeited
+    let env = mock_env_time(SECONDS_PER_PERIOD * 2);
+    let msg = ExecuteMsg::Renounce {
+        vesting_id: None,
+        keep_claimable: false,
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient1", &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(res.messages, vec![]);
+
+    // Forfeited amount is redistributed pro-rata to the total amounts of the active vestings,
+    // with the rounding remainder left in the contract
+    let expected_shares = [
+        ("recipient2", 300_000_000_000u128, 119_999_999_999u128),
+        ("recipient3", 150_000_000_001u128, 60_000_000_000u128),
+        ("recipient4", 150_000_000_000u128, 59_999_999_999u128),
+        ("recipient5", 75_000_000_001u128, 30_000_000_000u128),
+        ("recipient6", 75_000_000_000u128, 29_999_999_999u128),
+        ("recipient7", 1u128, 0u128),
+    ];
+    let mut total_share = 0u128;
+    for (recipient, total_amount, share) in expected_shares {
+        let vesting_info = query_vesting(deps.as_ref(), recipient.to_string());
+        assert_eq!(
+            vesting_info.total_amount,
+            Uint128::from(total_amount + share)
+        );
+        assert_eq!(
+            vesting_info.amount_per_period,
+            Uint128::from(total_amount + share) / Uint128::from(vesting_info.total_periods)
+        );
+        total_share += share;
+    }
+    assert_eq!(total_share, 299_999_999_997u128);
+
+    let msg = ExecuteMsg::UpdateConfig {
+        forfeit_address: None,
+        forfeit_mode: Some(ForfeitMode::Burn),
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("master_address", &[]),
+        msg,
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::Renounce {
+        vesting_id: None,
+        keep_claimable: false,
+    };
+    let res = execute(deps.as_mut(), env, mock_info("recipient7", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Burn {
+            amount: coins(1u128, "uluna"),
+        }))],
+    );
+    assert!(res
+        .attributes
+        .contains(&Attribute::new("burned_amount", "1")));
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Stats {}).unwrap();
+    let stats: Stats = from_binary(&res).unwrap();
+    assert_eq!(
+        stats,
+        Stats {
+            total_forfeited: Uint128::from(300000000002u128),
+            total_burned: Uint128::from(1u128),
+            total_expired: Uint128::zero(),
+        }
+    );
+}
+
+#[test]
+fn test_redistribute_eligible_vestings() {
+    let mut deps = mock_dependencies(&[]);
+
+    let vestings = ["recipient1", "recipient2", "recipient3", "recipient4"]
+        .iter()
+        .map(|recipient| Vesting {
+            recipient: recipient.to_string(),
+            amount: Uint128::from(300_000_000_000u128),
+            start_time: None,
+            cliff_duration: None,
+            mode: None,
+            tollgate_periods: None,
+            basket: None,
+        })
+        .collect();
+
+    let msg = InstantiateMsg {
+        master_address: Some("master_address".to_string()),
+        forfeit_address: None,
+        forfeit_mode: Some(ForfeitMode::Redistribute),
+        acceptance_period: Some(SECONDS_PER_PERIOD * 10),
+        claim_expiry_duration: None,
+        clock: None,
+        unbonding_period: None,
+        transferable: None,
+        vestings,
+    };
+
+    let info = mock_info("addr0000", &[coin(1_200_000_000_000u128, "uluna")]);
+    instantiate(deps.as_mut(), mock_env_time(0), info, msg).unwrap();
+
+    // recipient2 leaves its vesting pending acceptance
+    for recipient in ["recipient1", "recipient3", "recipient4"] {
+        execute(
+            deps.as_mut(),
+            mock_env_time(0),
+            mock_info(recipient, &[]),
+            ExecuteMsg::AcceptAllocation { vesting_id: None },
+        )
+        .unwrap();
+    }
+
+    // recipient3 claims its whole vesting
+    let env = mock_env_time(SECONDS_PER_PERIOD * 6);
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient3", &[]),
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::Renounce {
+        vesting_id: None,
+        keep_claimable: false,
+    };
+    execute(deps.as_mut(), env, mock_info("recipient4", &[]), msg).unwrap();
+
+    // Only the accepted vesting which is not fully claimed is credited
+    let vesting_info = query_vesting(deps.as_ref(), "recipient1".to_string());
+    assert_eq!(
+        vesting_info.total_amount,
+        Uint128::from(600_000_000_000u128)
+    );
+    assert_eq!(
+        vesting_info.vested_amount,
+        Uint128::from(600_000_000_000u128)
+    );
+    for recipient in ["recipient2", "recipient3"] {
+        let vesting_info = query_vesting(deps.as_ref(), recipient.to_string());
+        assert_eq!(
+            vesting_info.total_amount,
+            Uint128::from(300_000_000_000u128)
+        );
+    }
+}
+
+#[test]
+fn test_accept_and_reclaim_unaccepted() {
+    let mut deps = mock_dependencies(&[]);
+
+    let vestings = vec![
+        Vesting {
+            recipient: "recipient1".to_string(),
+            amount: Uint128::from(300_000_000_001u128),
+            start_time: None,
+            cliff_duration: None,
+            mode: None,
+            tollgate_periods: None,
+            basket: None,
+        },
+        Vesting {
+            recipient: "recipient2".to_string(),
+            amount: Uint128::from(300_000_000_000u128),
+            start_time: None,
+            cliff_duration: None,
+            mode: None,
+            tollgate_periods: None,
+            basket: None,
+        },
+    ];
+
+    let msg = InstantiateMsg {
+        master_address: Some("master_address".to_string()),
+        forfeit_address: None,
+        forfeit_mode: None,
+        acceptance_period: Some(SECONDS_PER_PERIOD),
+        claim_expiry_duration: None,
+        clock: None,
+        unbonding_period: None,
+        transferable: None,
+        vestings,
+    };
+
+    let info = mock_info("addr0000", &[coin(600_000_000_001u128, "uluna")]);
+    instantiate(deps.as_mut(), mock_env_time(0), info, msg).unwrap();
+    assert_eq!(
+        query_vesting(deps.as_ref(), "recipient1".to_string()).acceptance_deadline,
+        Some(SECONDS_PER_PERIOD)
+    );
+
+    // Claims are blocked until the vesting is accepted
+    let env = mock_env_time(SECONDS_PER_PERIOD - 1);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient1", &[]),
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::PendingAcceptance {});
+
+    let msg = ExecuteMsg::ReclaimUnaccepted {
+        recipient: "recipient2".to_string(),
+        vesting_id: None,
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("master_address", &[]),
+        msg,
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::AcceptanceDeadlineNotReached {});
+
+    let msg = ExecuteMsg::AcceptAllocation { vesting_id: None };
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient1", &[]),
+        msg.clone(),
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("recipient1", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::NotPendingAcceptance {});
+
+    let env = mock_env_time(SECONDS_PER_PERIOD);
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient1", &[]),
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap();
+
+    // The deadline for recipient2 has passed
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient2", &[]),
+        msg,
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::AcceptanceDeadlinePassed {});
+
+    let msg = ExecuteMsg::ReclaimUnaccepted {
+        recipient: "recipient2".to_string(),
+        vesting_id: None,
+    };
+    let res = execute(deps.as_mut(), env, mock_info("master_address", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "master_address".to_string(),
+            amount: coins(300000000000u128, "uluna"),
+        }))],
+    );
+    let vesting_info = query_vesting(deps.as_ref(), "recipient2".to_string());
+    assert!(!vesting_info.active);
+    assert_eq!(vesting_info.vested_amount, Uint128::zero());
+}
+
+#[test]
+fn test_expire_vesting() {
+    let mut deps = mock_dependencies(&[]);
+
+    let vestings = vec![Vesting {
+        recipient: "recipient1".to_string(),
+        amount: Uint128::from(300_000_000_001u128),
+        start_time: None,
+        cliff_duration: None,
+        mode: None,
+        tollgate_periods: None,
+        basket: None,
+    }];
+
+    let msg = InstantiateMsg {
+        master_address: Some("master_address".to_string()),
+        forfeit_address: None,
+        forfeit_mode: None,
+        acceptance_period: None,
+        claim_expiry_duration: Some(SECONDS_PER_PERIOD),
+        clock: None,
+        unbonding_period: None,
+        transferable: None,
+        vestings,
+    };
+
+    let info = mock_info("addr0000", &[coin(300_000_000_001u128, "uluna")]);
+    instantiate(deps.as_mut(), mock_env_time(0), info, msg).unwrap();
+
+    let env = mock_env_time(SECONDS_PER_PERIOD * 2);
+    execute(
+        deps.as_mut(),
+        env,
+        mock_info("recipient1", &[]),
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap();
+
+    // Unclaimed amount expires one period after the final period
+    let env = mock_env_time(SECONDS_PER_PERIOD * 7 - 1);
+    let msg = ExecuteMsg::ExpireVesting {
+        recipient: "recipient1".to_string(),
+        vesting_id: None,
+    };
+    let res = execute(deps.as_mut(), env, mock_info("anyone", &[]), msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::ClaimExpiryNotReached {});
+
+    let env = mock_env_time(SECONDS_PER_PERIOD * 7);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient1", &[]),
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::VestingExpired {});
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("anyone", &[]),
+        msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "master_address".to_string(),
+            amount: coins(200000000001u128, "uluna"),
+        }))],
+    );
+    let vesting_info = query_vesting(deps.as_ref(), "recipient1".to_string());
+    assert!(vesting_info.expired);
+    assert!(!vesting_info.active);
+    assert_eq!(vesting_info.vested_amount, Uint128::zero());
+
+    let res = execute(deps.as_mut(), env, mock_info("anyone", &[]), msg).unwrap_err();
+    assert_eq!(res, ContractError::NothingToExpire {});
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Stats {}).unwrap();
+    let stats: Stats = from_binary(&res).unwrap();
+    assert_eq!(
+        stats,
+        Stats {
+            total_forfeited: Uint128::from(200000000001u128),
+            total_burned: Uint128::zero(),
+            total_expired: Uint128::from(200000000001u128),
+        }
+    );
+}
+
+#[test]
+fn test_sweep_excess() {
+    let (mut deps, _) = mock_init();
+
+    // Funds backing the vestings are not excess
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![coin(600_000_000_001u128, "uluna")]);
+    let msg = ExecuteMsg::SweepExcess {
+        denom: "uluna".to_string(),
+        to: "treasury".to_string(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("master_address", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::NoExcess {});
+
+    // Only admins can sweep
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR,
+        vec![coin(600_000_000_011u128, "uluna"), coin(5u128, "uusd")],
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("recipient1", &[]),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("master_address", &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "treasury".to_string(),
+            amount: coins(10u128, "uluna"),
+        }))],
+    );
+
+    // Other denoms are swept entirely
+    let msg = ExecuteMsg::SweepExcess {
+        denom: "uusd".to_string(),
+        to: "treasury".to_string(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("master_address", &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "treasury".to_string(),
+            amount: coins(5u128, "uusd"),
+        }))],
+    );
+}
+
+#[test]
+fn test_unexpected_funds() {
+    let (mut deps, _) = mock_init();
+
+    let msgs = vec![
+        ExecuteMsg::UpdateConfig {
+            forfeit_address: None,
+            forfeit_mode: None,
+        },
+        ExecuteMsg::GrantRole {
+            role: Role::Reviewer,
+            address: "reviewer".to_string(),
+        },
+        ExecuteMsg::RevokeRole {
+            role: Role::Reviewer,
+            address: "master_address".to_string(),
+        },
+        ExecuteMsg::SweepExcess {
+            denom: "uluna".to_string(),
+            to: "treasury".to_string(),
+        },
+        ExecuteMsg::ApproveTollgate {
+            recipient: "recipient1".to_string(),
+            vesting_id: None,
+            approve: true,
+            payout: None,
+        },
+        ExecuteMsg::ReclaimUnaccepted {
+            recipient: "recipient1".to_string(),
+            vesting_id: None,
+        },
+        ExecuteMsg::Pause {
+            recipient: None,
+            auto_unpause_time: None,
+        },
+        ExecuteMsg::Unpause { recipient: None },
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+        ExecuteMsg::AcceptAllocation { vesting_id: None },
+        ExecuteMsg::Renounce {
+            vesting_id: None,
+            keep_claimable: true,
+        },
+        ExecuteMsg::ResolveIbcPayout {
+            payout_id: 1u64,
+            delivered: true,
+        },
+        ExecuteMsg::SplitVesting {
+            vesting_id: None,
+            recipient_shares: vec![("team_a".to_string(), Uint128::new(1u128))],
+        },
+        ExecuteMsg::MergeVestings {
+            vesting_ids: vec![1u64, 2u64],
+        },
+        ExecuteMsg::Delegate {
+            vesting_id: None,
+            validator: "validator1".to_string(),
+            amount: Uint128::from(1u128),
+        },
+        ExecuteMsg::Redelegate {
+            vesting_id: None,
+            src_validator: "validator1".to_string(),
+            dst_validator: "validator2".to_string(),
+            amount: Uint128::from(1u128),
+        },
+        ExecuteMsg::Undelegate {
+            vesting_id: None,
+            validator: "validator1".to_string(),
+            amount: Uint128::from(1u128),
+        },
+        ExecuteMsg::WithdrawRewards {},
+        ExecuteMsg::RetryPayout {},
+        ExecuteMsg::SetClaimHook { hook: None },
+        ExecuteMsg::Vote {
+            proposal_id: 1u64,
+            vote: VoteOption::Yes,
+        },
+        ExecuteMsg::TransferNft {
+            recipient: "buyer".to_string(),
+            token_id: "1".to_string(),
+        },
+        ExecuteMsg::SendNft {
+            contract: "market".to_string(),
+            token_id: "1".to_string(),
+            msg: Binary::default(),
+        },
+        ExecuteMsg::ExpireVesting {
+            recipient: "recipient1".to_string(),
+            vesting_id: None,
+        },
+        ExecuteMsg::SettleForfeit {
+            recipient: "recipient1".to_string(),
+            vesting_id: None,
+        },
+    ];
+
+    let env = mock_env_time(SECONDS_PER_PERIOD * 2);
+    for msg in msgs {
+        let info = mock_info("master_address", &[coin(1u128, "uluna")]);
+        let res = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+        assert_eq!(res, ContractError::UnexpectedFunds {}, "{:?}", msg);
+
+        let info = mock_info("recipient1", &[coin(1u128, "uusd")]);
+        let res = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+        assert_eq!(res, ContractError::UnexpectedFunds {}, "{:?}", msg);
+    }
+
+    // Funding messages still take payment
+    let msg = ExecuteMsg::TopUpVesting {
+        recipient: "recipient1".to_string(),
+        vesting_id: None,
+    };
+    let info = mock_info("master_address", &[coin(1u128, "uluna")]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::AddVestings {
+        vestings: vec![Vesting {
+            recipient: "recipient3".to_string(),
+            amount: Uint128::from(1u128),
+            start_time: None,
+            cliff_duration: None,
+            mode: None,
+            tollgate_periods: None,
+            basket: None,
+        }],
+    };
+    let info = mock_info("master_address", &[coin(1u128, "uluna")]);
+    execute(deps.as_mut(), env, info, msg).unwrap();
+}
+
+#[test]
+fn test_start_time_and_cliff() {
+    let (mut deps, _) = mock_init();
+
+    let msg = ExecuteMsg::AddVestings {
+        vestings: vec![Vesting {
+            recipient: "recipient3".to_string(),
+            amount: Uint128::from(30_000_000_000u128),
+            start_time: Some(SECONDS_PER_PERIOD * 10),
+            cliff_duration: Some(SECONDS_PER_PERIOD * 2),
+            mode: None,
+            tollgate_periods: None,
+            basket: None,
+        }],
+    };
+    let info = mock_info("master_address", &[coin(30_000_000_000u128, "uluna")]);
+    execute(deps.as_mut(), mock_env_time(0), info, msg).unwrap();
+
+    let vesting_info = query_vesting(deps.as_ref(), "recipient3".to_string());
+    assert_eq!(vesting_info.start_time, SECONDS_PER_PERIOD * 10);
+    assert_eq!(vesting_info.cliff_duration, SECONDS_PER_PERIOD * 2);
+
+    // Nothing can be claimed before the start time, nor during the cliff
+    let info = mock_info("recipient3", &[]);
+    let env = mock_env_time(SECONDS_PER_PERIOD * 3);
+    let res = execute(
+        deps.as_mut(),
+        env,
+        info.clone(),
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::NoClaimable {});
+
+    let env = mock_env_time(SECONDS_PER_PERIOD * 12 - 1);
+    let res = execute(
+        deps.as_mut(),
+        env,
+        info.clone(),
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::NoClaimable {});
+
+    // Periods accrued during the cliff unlock at once
+    let env = mock_env_time(SECONDS_PER_PERIOD * 12);
+    let res = execute(
+        deps.as_mut(),
+        env,
+        info,
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient3".to_string(),
+                amount: coins(20000000000u128, "uluna"),
+            }),
+            0u64
+        )],
+    );
+    assert_eq!(
+        query_vesting(deps.as_ref(), "recipient3".to_string()).last_claimed_period,
+        2u64
+    );
+}
+
+#[test]
+fn test_linear_mode() {
+    let (mut deps, _) = mock_init();
+
+    let msg = ExecuteMsg::AddVestings {
+        vestings: vec![Vesting {
+            recipient: "recipient3".to_string(),
+            amount: Uint128::from(30_000_000_001u128),
+            start_time: None,
+            cliff_duration: None,
+            mode: Some(VestingMode::Linear),
+            tollgate_periods: None,
+            basket: None,
+        }],
+    };
+    let info = mock_info("master_address", &[coin(30_000_000_001u128, "uluna")]);
+    execute(deps.as_mut(), mock_env_time(0), info, msg).unwrap();
+
+    // Claimable amount grows per second, rounded down
+    let info = mock_info("recipient3", &[]);
+    let env = mock_env_time(SECONDS_PER_PERIOD / 2);
+    let res = execute(
+        deps.as_mut(),
+        env,
+        info.clone(),
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient3".to_string(),
+                amount: coins(5000000000u128, "uluna"),
+            }),
+            0u64
+        )],
+    );
+
+    let env = mock_env_time(SECONDS_PER_PERIOD * 3 - 1);
+    let res = execute(
+        deps.as_mut(),
+        env,
+        info.clone(),
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient3".to_string(),
+                amount: coins(24999996142u128, "uluna"),
+            }),
+            0u64
+        )],
+    );
+
+    // The total amount is unlocked exactly at the final second
+    let env = mock_env_time(SECONDS_PER_PERIOD * 3);
+    let res = execute(
+        deps.as_mut(),
+        env,
+        info.clone(),
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient3".to_string(),
+                amount: coins(3859u128, "uluna"),
+            }),
+            0u64
+        )],
+    );
+
+    let vesting_info = query_vesting(deps.as_ref(), "recipient3".to_string());
+    assert_eq!(
+        vesting_info.claimed_amount,
+        Uint128::from(30_000_000_001u128)
+    );
+    assert_eq!(vesting_info.vested_amount, Uint128::zero());
+    assert_eq!(vesting_info.last_claimed_period, 3u64);
+
+    let env = mock_env_time(SECONDS_PER_PERIOD * 10);
+    let res = execute(
+        deps.as_mut(),
+        env,
+        info,
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::NoClaimable {});
+}
+
+#[test]
+fn test_custom_unlock_steps() {
+    let (mut deps, _) = mock_init();
+
+    let steps = vec![
+        UnlockStep::Period {
+            period: 1,
+            amount: Uint128::from(10_000_000_000u128),
+        },
+        UnlockStep::Period {
+            period: 2,
+            amount: Uint128::from(20_000_000_000u128),
+        },
+        UnlockStep::Period {
+            period: 3,
+            amount: Uint128::from(70_000_000_000u128),
+        },
+    ];
+    let vesting = Vesting {
+        recipient: "recipient3".to_string(),
+        amount: Uint128::from(100_000_000_000u128),
+        start_time: None,
+        cliff_duration: None,
+        mode: Some(VestingMode::Custom {
+            steps: steps.clone(),
+        }),
+        tollgate_periods: Some(vec![3]),
+        basket: None,
+    };
+    let info = mock_info("master_address", &[coin(100_000_000_000u128, "uluna")]);
+
+    // Tollgates must be within the vesting periods
+    let msg = ExecuteMsg::AddVestings {
+        vestings: vec![vesting.clone()],
+    };
+    let res = execute(deps.as_mut(), mock_env_time(0), info.clone(), msg).unwrap_err();
+    assert_eq!(res, ContractError::InvalidTollgatePeriods {});
+
+    // Steps must sum to the vesting amount
+    let msg = ExecuteMsg::AddVestings {
+        vestings: vec![Vesting {
+            mode: Some(VestingMode::Custom {
+                steps: steps[1..].to_vec(),
+            }),
+            tollgate_periods: Some(vec![2]),
+            ..vesting.clone()
+        }],
+    };
+    let res = execute(deps.as_mut(), mock_env_time(0), info.clone(), msg).unwrap_err();
+    assert_eq!(res, ContractError::InvalidUnlockSteps {});
+
+    let msg = ExecuteMsg::AddVestings {
+        vestings: vec![
+            Vesting {
+                tollgate_periods: Some(vec![2]),
+                ..vesting.clone()
+            },
+            Vesting {
+                recipient: "recipient4".to_string(),
+                amount: Uint128::from(30_000_000_000u128),
+                mode: Some(VestingMode::Custom {
+                    steps: vec![
+                        UnlockStep::Time {
+                            time: SECONDS_PER_PERIOD / 2,
+                            amount: Uint128::from(10_000_000_000u128),
+                        },
+                        UnlockStep::Period {
+                            period: 3,
+                            amount: Uint128::from(20_000_000_000u128),
+                        },
+                    ],
+                }),
+                tollgate_periods: Some(vec![1]),
+                ..vesting
+            },
+        ],
+    };
+    let info = mock_info("master_address", &[coin(130_000_000_000u128, "uluna")]);
+    execute(deps.as_mut(), mock_env_time(0), info, msg).unwrap();
+
+    let vesting_info = query_vesting(deps.as_ref(), "recipient3".to_string());
+    assert_eq!(vesting_info.approved_periods, 2u64);
+    assert_eq!(vesting_info.total_periods, 3u64);
+
+    // Steps past the approved tollgate stay locked
+    let env = mock_env_time(SECONDS_PER_PERIOD * 3);
+    let info = mock_info("recipient3", &[]);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient3".to_string(),
+                amount: coins(30000000000u128, "uluna"),
+            }),
+            0u64
+        )],
+    );
+
+    let msg = ExecuteMsg::ApproveTollgate {
+        recipient: "recipient3".to_string(),
+        vesting_id: None,
+        approve: true,
+        payout: None,
+    };
+    let master = mock_info("master_address", &[]);
+    execute(deps.as_mut(), env.clone(), master.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        query_vesting(deps.as_ref(), "recipient3".to_string()).approved_periods,
+        3u64
+    );
+    let res = execute(deps.as_mut(), env.clone(), master.clone(), msg).unwrap_err();
+    assert_eq!(res, ContractError::NoTollgateRequired {});
+
+    let res = execute(
+        deps.as_mut(),
+        env,
+        info,
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient3".to_string(),
+                amount: coins(70000000000u128, "uluna"),
+            }),
+            0u64
+        )],
+    );
+
+    // Disapproving the tollgate forfeits the steps past it
+    let msg = ExecuteMsg::ApproveTollgate {
+        recipient: "recipient4".to_string(),
+        vesting_id: None,
+        approve: false,
+        payout: None,
+    };
+    let res = execute(deps.as_mut(), mock_env_time(0), master.clone(), msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::NextTollgateTimeNotReached {});
+
+    let env = mock_env_time(SECONDS_PER_PERIOD);
+    let res = execute(deps.as_mut(), env.clone(), master, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "master_address".to_string(),
+            amount: coins(20000000000u128, "uluna"),
+        }))],
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("recipient4", &[]),
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient4".to_string(),
+                amount: coins(10000000000u128, "uluna"),
+            }),
+            0u64
+        )],
+    );
+}
+
+#[test]
+fn test_custom_unlock_steps_default_tollgates() {
+    let (mut deps, _) = mock_init();
+
+    let msg = ExecuteMsg::AddVestings {
+        vestings: vec![Vesting {
+            recipient: "recipient3".to_string(),
+            amount: Uint128::from(100_000_000_000u128),
+            start_time: None,
+            cliff_duration: None,
+            mode: Some(VestingMode::Custom {
+                steps: vec![
+                    UnlockStep::Period {
+                        period: 4,
+                        amount: Uint128::from(50_000_000_000u128),
+                    },
+                    UnlockStep::Period {
+                        period: 8,
+                        amount: Uint128::from(50_000_000_000u128),
+                    },
+                ],
+            }),
+            tollgate_periods: None,
+            basket: None,
+        }],
+    };
+    let info = mock_info("master_address", &[coin(100_000_000_000u128, "uluna")]);
+    execute(deps.as_mut(), mock_env_time(0), info, msg).unwrap();
+
+    let vesting_info = query_vesting(deps.as_ref(), "recipient3".to_string());
+    assert_eq!(vesting_info.approved_periods, 6u64);
+    assert_eq!(vesting_info.total_periods, 8u64);
+
+    // The last default tollgate is at the end of the vesting, even if it is not a full toll away
+    let msg = ExecuteMsg::ApproveTollgate {
+        recipient: "recipient3".to_string(),
+        vesting_id: None,
+        approve: true,
+        payout: None,
+    };
+    let env = mock_env_time(SECONDS_PER_PERIOD * 8);
+    let master = mock_info("master_address", &[]);
+    execute(deps.as_mut(), env.clone(), master.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        query_vesting(deps.as_ref(), "recipient3".to_string()).approved_periods,
+        8u64
+    );
+    let res = execute(deps.as_mut(), env.clone(), master, msg).unwrap_err();
+    assert_eq!(res, ContractError::NoTollgateRequired {});
+
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("recipient3", &[]),
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient3".to_string(),
+                amount: coins(100000000000u128, "uluna"),
+            }),
+            0u64
+        )],
+    );
+}
+
+#[test]
+fn test_milestone_mode() {
+    let (mut deps, _) = mock_init();
+
+    let vesting = Vesting {
+        recipient: "recipient3".to_string(),
+        amount: Uint128::from(100_000_000_000u128),
+        start_time: None,
+        cliff_duration: None,
+        mode: Some(VestingMode::Milestones {
+            tranches: vec![
+                Uint128::from(40_000_000_000u128),
+                Uint128::from(60_000_000_000u128),
+            ],
+        }),
+        tollgate_periods: None,
+        basket: None,
+    };
+    let info = mock_info("master_address", &[coin(100_000_000_000u128, "uluna")]);
+
+    // Tranches must sum to the vesting amount
+    let msg = ExecuteMsg::AddVestings {
+        vestings: vec![Vesting {
+            mode: Some(VestingMode::Milestones {
+                tranches: vec![Uint128::from(40_000_000_000u128)],
+            }),
+            ..vesting.clone()
+        }],
+    };
+    let res = execute(deps.as_mut(), mock_env_time(0), info.clone(), msg).unwrap_err();
+    assert_eq!(res, ContractError::InvalidMilestoneTranches {});
+
+    let msg = ExecuteMsg::AddVestings {
+        vestings: vec![vesting],
+    };
+    execute(deps.as_mut(), mock_env_time(0), info, msg).unwrap();
+
+    let vesting_info = query_vesting(deps.as_ref(), "recipient3".to_string());
+    assert_eq!(vesting_info.approved_periods, 0u64);
+    assert_eq!(vesting_info.total_periods, 2u64);
+
+    // Nothing unlocks with time alone
+    let info = mock_info("recipient3", &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env_time(SECONDS_PER_PERIOD * 10),
+        info.clone(),
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::NoClaimable {});
+
+    // The first milestone is approved right away and claimed separately
+    let master = mock_info("master_address", &[]);
+    let msg = ExecuteMsg::ApproveTollgate {
+        recipient: "recipient3".to_string(),
+        vesting_id: None,
+        approve: true,
+        payout: None,
+    };
+    let res = execute(deps.as_mut(), mock_env_time(1), master.clone(), msg).unwrap();
+    assert!(res.messages.is_empty());
+    let res = execute(
+        deps.as_mut(),
+        mock_env_time(1),
+        info,
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient3".to_string(),
+                amount: coins(40_000_000_000u128, "uluna"),
+            }),
+            0u64
+        )],
+    );
+
+    // The second milestone is paid out on approval
+    let msg = ExecuteMsg::ApproveTollgate {
+        recipient: "recipient3".to_string(),
+        vesting_id: None,
+        approve: true,
+        payout: Some(true),
+    };
+    let res = execute(deps.as_mut(), mock_env_time(2), master.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient3".to_string(),
+                amount: coins(60_000_000_000u128, "uluna"),
+            }),
+            0u64
+        )],
+    );
+
+    let vesting_info = query_vesting(deps.as_ref(), "recipient3".to_string());
+    assert_eq!(vesting_info.approved_periods, 2u64);
+    assert_eq!(vesting_info.last_claimed_period, 2u64);
+    assert_eq!(
+        vesting_info.claimed_amount,
+        Uint128::from(100_000_000_000u128)
+    );
+    assert_eq!(vesting_info.vested_amount, Uint128::zero());
+
+    let res = execute(deps.as_mut(), mock_env_time(3), master, msg).unwrap_err();
+    assert_eq!(res, ContractError::NoTollgateRequired {});
+}
+
+#[test]
+fn test_height_clock() {
+    let mut deps = mock_dependencies(&[]);
+    let env_at = |height: u64| {
+        let mut env = mock_env_time(0);
+        env.block.height = height;
+        env
+    };
+
+    let mut msg = InstantiateMsg {
+        master_address: Some("master_address".to_string()),
+        forfeit_address: None,
+        forfeit_mode: None,
+        acceptance_period: None,
+        claim_expiry_duration: None,
+        clock: Some(Clock::Height {
+            blocks_per_period: 0,
+        }),
+        unbonding_period: None,
+        transferable: None,
+        vestings: vec![Vesting {
+            recipient: "recipient1".to_string(),
+            amount: Uint128::from(30_000_000_000u128),
+            start_time: None,
+            cliff_duration: None,
+            mode: None,
+            tollgate_periods: None,
+            basket: None,
+        }],
+    };
+    let info = mock_info("addr0000", &[coin(30_000_000_000u128, "uluna")]);
+    let res = instantiate(deps.as_mut(), env_at(1_000), info.clone(), msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::InvalidClock {});
+
+    msg.clock = Some(Clock::Height {
+        blocks_per_period: 100,
+    });
+    instantiate(deps.as_mut(), env_at(1_000), info, msg).unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(config.vesting_start_time, 1_000u64);
+    assert_eq!(
+        config.clock,
+        Clock::Height {
+            blocks_per_period: 100
+        }
+    );
+
+    // Block time does not count towards the periods
+    let info = mock_info("recipient1", &[]);
+    let mut env = env_at(1_099);
+    env.block.time = Timestamp::from_seconds(SECONDS_PER_PERIOD * 3);
+    let res = execute(
+        deps.as_mut(),
+        env,
+        info.clone(),
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::NoClaimable {});
+
+    let res = execute(
+        deps.as_mut(),
+        env_at(1_200),
+        info,
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient1".to_string(),
+                amount: coins(20_000_000_000u128, "uluna"),
+            }),
+            0u64
+        )],
+    );
+    assert_eq!(
+        query_vesting(deps.as_ref(), "recipient1".to_string()).last_claimed_period,
+        2u64
+    );
+}
+
+#[test]
+fn test_calendar_month_clock() {
+    let mut deps = mock_dependencies(&[]);
+
+    // 2024-01-31 12:00:00 UTC
+    let start_time = 1_706_659_200u64 + 43_200u64;
+    let msg = InstantiateMsg {
+        master_address: Some("master_address".to_string()),
+        forfeit_address: None,
+        forfeit_mode: None,
+        acceptance_period: None,
+        claim_expiry_duration: None,
+        clock: Some(Clock::CalendarMonth),
+        unbonding_period: None,
+        transferable: None,
+        vestings: vec![
+            Vesting {
+                recipient: "recipient1".to_string(),
+                amount: Uint128::from(30_000_000_000u128),
+                start_time: None,
+                cliff_duration: None,
+                mode: None,
+                tollgate_periods: None,
+                basket: None,
+            },
+            Vesting {
+                recipient: "recipient2".to_string(),
+                amount: Uint128::from(30_000_000_000u128),
+                start_time: None,
+                cliff_duration: Some(SECONDS_PER_PERIOD * 2),
+                mode: Some(VestingMode::Custom {
+                    steps: vec![
+                        UnlockStep::Period {
+                            period: 1,
+                            amount: Uint128::from(10_000_000_000u128),
+                        },
+                        UnlockStep::Time {
+                            time: start_time + 10,
+                            amount: Uint128::from(20_000_000_000u128),
+                        },
+                    ],
+                }),
+                tollgate_periods: None,
+                basket: None,
+            },
+        ],
+    };
+    let info = mock_info("addr0000", &[coin(60_000_000_000u128, "uluna")]);
+    instantiate(deps.as_mut(), mock_env_time(start_time), info, msg).unwrap();
+
+    // Periods end on the same day of each month, or the last day of shorter months
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Schedule {
+            recipient: "recipient1".to_string(),
+            vesting_id: None,
+        },
+    )
+    .unwrap();
+    let schedule: ScheduleResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        schedule.unlocks,
+        vec![
+            // 2024-02-29 12:00:00 UTC
+            ScheduledUnlock {
+                period: 1,
+                time: Some(1_709_164_800u64 + 43_200u64),
+                amount: Uint128::from(10_000_000_000u128),
+            },
+            // 2024-03-31 12:00:00 UTC
+            ScheduledUnlock {
+                period: 2,
+                time: Some(1_711_843_200u64 + 43_200u64),
+                amount: Uint128::from(10_000_000_000u128),
+            },
+            // 2024-04-30 12:00:00 UTC
+            ScheduledUnlock {
+                period: 3,
+                time: Some(1_714_435_200u64 + 43_200u64),
+                amount: Uint128::from(10_000_000_000u128),
+            },
+        ]
+    );
+
+    // Unlocks accrued during the cliff are scheduled at its end
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Schedule {
+            recipient: "recipient2".to_string(),
+            vesting_id: None,
+        },
+    )
+    .unwrap();
+    let schedule: ScheduleResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        schedule.unlocks,
+        vec![
+            ScheduledUnlock {
+                period: 1,
+                time: Some(start_time + SECONDS_PER_PERIOD * 2),
+                amount: Uint128::from(10_000_000_000u128),
+            },
+            ScheduledUnlock {
+                period: 1,
+                time: Some(start_time + SECONDS_PER_PERIOD * 2),
+                amount: Uint128::from(20_000_000_000u128),
+            },
+        ]
+    );
+
+    let info = mock_info("recipient1", &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env_time(1_709_164_800u64 + 43_199u64),
+        info.clone(),
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::NoClaimable {});
+
+    // 2024-03-31 11:59:59 UTC is still within the second period
+    let res = execute(
+        deps.as_mut(),
+        mock_env_time(1_711_843_200u64 + 43_199u64),
+        info,
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient1".to_string(),
+                amount: coins(10_000_000_000u128, "uluna"),
+            }),
+            0u64
+        )],
+    );
+}
+
+#[test]
+fn test_multiple_positions() {
+    let (mut deps, _) = mock_init();
+
+    // A recipient can hold several positions, each with its own vesting id
+    let msg = ExecuteMsg::AddVestings {
+        vestings: vec![Vesting {
+            recipient: "recipient1".to_string(),
+            amount: Uint128::from(30_000_000_000u128),
+            start_time: None,
+            cliff_duration: None,
+            mode: None,
+            tollgate_periods: None,
+            basket: None,
+        }],
+    };
+    let info = mock_info("master_address", &[coin(30_000_000_000u128, "uluna")]);
+    execute(deps.as_mut(), mock_env_time(0), info, msg).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Vestings {
+            recipient: "recipient1".to_string(),
+        },
+    )
+    .unwrap();
+    let vestings: VestingsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        vestings
+            .vestings
+            .iter()
+            .map(|vesting_info| (vesting_info.vesting_id, vesting_info.total_amount))
+            .collect::<Vec<_>>(),
+        vec![
+            (1u64, Uint128::from(300_000_000_001u128)),
+            (3u64, Uint128::from(30_000_000_000u128)),
+        ]
+    );
+
+    // The position must be specified once there are several
+    let msg = ExecuteMsg::TopUpVesting {
+        recipient: "recipient1".to_string(),
+        vesting_id: None,
+    };
+    let info = mock_info("master_address", &[coin(1u128, "uluna")]);
+    let res = execute(deps.as_mut(), mock_env_time(0), info, msg).unwrap_err();
+    assert_eq!(res, ContractError::AmbiguousVesting {});
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::VestingInfo {
+            recipient: "recipient1".to_string(),
+            vesting_id: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        res,
+        StdError::generic_err(ContractError::AmbiguousVesting {}.to_string())
+    );
+    assert_eq!(
+        query_vesting(deps.as_ref(), "recipient2".to_string()).vesting_id,
+        2u64
+    );
+
+    // Claims from the specified positions only
+    let info = mock_info("recipient1", &[]);
+    let msg = ExecuteMsg::Claim {
+        vesting_ids: Some(vec![3, 3]),
+        ibc: None,
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env_time(SECONDS_PER_PERIOD),
+        info.clone(),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient1".to_string(),
+                amount: coins(10_000_000_000u128, "uluna"),
+            }),
+            0u64
+        )],
+    );
+
+    // Claims across all positions
+    let msg = ExecuteMsg::Claim {
+        vesting_ids: None,
+        ibc: None,
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env_time(SECONDS_PER_PERIOD * 2),
+        info,
+        msg,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient1".to_string(),
+                amount: coins(110_000_000_000u128, "uluna"),
+            }),
+            0u64
+        )],
+    );
+}
+
+#[test]
+fn test_basket() {
+    let (mut deps, _) = mock_init();
+
+    let vesting = Vesting {
+        recipient: "recipient3".to_string(),
+        amount: Uint128::from(30_000_000_000u128),
+        start_time: None,
+        cliff_duration: None,
+        mode: None,
+        tollgate_periods: None,
+        basket: Some(vec![coin(3_000u128, "uusd")]),
+    };
+    let add_vestings = |vesting: &Vesting| ExecuteMsg::AddVestings {
+        vestings: vec![vesting.clone()],
+    };
+
+    // The sent funds must match the sums per denom
+    let info = mock_info("master_address", &[coin(30_000_000_000u128, "uluna")]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env_time(0),
+        info,
+        add_vestings(&vesting),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::MismatchedAssetType {});
+
+    let info = mock_info(
+        "master_address",
+        &[coin(30_000_000_000u128, "uluna"), coin(2_000u128, "uusd")],
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env_time(0),
+        info,
+        add_vestings(&vesting),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::MismatchedAssetAmount {});
+
+    // Basket coins cannot be of the vesting denom
+    let info = mock_info("master_address", &[coin(30_000_003_000u128, "uluna")]);
+    let msg = add_vestings(&Vesting {
+        basket: Some(vec![coin(3_000u128, "uluna")]),
+        ..vesting.clone()
+    });
+    let res = execute(deps.as_mut(), mock_env_time(0), info, msg).unwrap_err();
+    assert_eq!(res, ContractError::InvalidBasket {});
+
+    let info = mock_info(
+        "master_address",
+        &[coin(30_000_000_000u128, "uluna"), coin(3_000u128, "uusd")],
+    );
+    execute(
+        deps.as_mut(),
+        mock_env_time(0),
+        info,
+        add_vestings(&vesting),
+    )
+    .unwrap();
+
+    // All due coins are claimed in a single transfer
+    let info = mock_info("recipient3", &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env_time(SECONDS_PER_PERIOD),
+        info.clone(),
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient3".to_string(),
+                amount: vec![coin(10_000_000_000u128, "uluna"), coin(1_000u128, "uusd")],
+            }),
+            0u64
+        )],
+    );
+
+    let vesting_info = query_vesting(deps.as_ref(), "recipient3".to_string());
+    assert_eq!(
+        vesting_info.basket,
+        vec![BasketCoin {
+            denom: "uusd".to_string(),
+            total_amount: Uint128::from(3_000u128),
+            amount_per_period: Uint128::from(1_000u128),
+            claimed_amount: Uint128::from(1_000u128),
+            vested_amount: Uint128::from(2_000u128),
+        }]
+    );
+
+    // The basket is still backing the vesting
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, vec![coin(2_500u128, "uusd")]);
+    let msg = ExecuteMsg::SweepExcess {
+        denom: "uusd".to_string(),
+        to: "master_address".to_string(),
+    };
+    let master = mock_info("master_address", &[]);
+    let res = execute(deps.as_mut(), mock_env_time(0), master, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "master_address".to_string(),
+            amount: coins(500u128, "uusd"),
+        }))],
+    );
+
+    // The unvested basket is forfeited along with the vesting
+    let msg = ExecuteMsg::Renounce {
+        vesting_id: None,
+        keep_claimable: false,
+    };
+    let res = execute(deps.as_mut(), mock_env_time(SECONDS_PER_PERIOD), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "master_address".to_string(),
+            amount: vec![coin(20_000_000_000u128, "uluna"), coin(2_000u128, "uusd")],
+        }))],
+    );
+    assert_eq!(
+        query_vesting(deps.as_ref(), "recipient3".to_string()).basket[0].vested_amount,
+        Uint128::zero()
+    );
+}
+
+#[test]
+fn test_staking() {
+    let (mut deps, _) = mock_init();
+    let validator = |address: &str| Validator {
+        address: address.to_string(),
+        commission: Decimal::percent(5),
+        max_commission: Decimal::percent(10),
+        max_change_rate: Decimal::percent(1),
+    };
+    let validators = [validator("validator1"), validator("validator2")];
+    deps.querier.update_staking("uluna", &validators, &[]);
+    let delegate = |validator: &str, amount: u128| ExecuteMsg::Delegate {
+        vesting_id: None,
+        validator: validator.to_string(),
+        amount: Uint128::from(amount),
+    };
+    let claim = ExecuteMsg::Claim {
+        vesting_ids: None,
+        ibc: None,
+    };
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_time(0),
+        mock_info("recipient1", &[]),
+        delegate("validator3", 1u128),
+    )
+    .unwrap_err();
+    assert_eq!(
+        res,
+        ContractError::ValidatorNotFound {
+            validator: "validator3".to_string()
+        }
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_time(0),
+        mock_info("recipient1", &[]),
+        delegate("validator1", 0u128),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::ZeroStakingAmount {});
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env_time(0),
+        mock_info("recipient1", &[]),
+        delegate("validator1", 300_000_000_002u128),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::InsufficientLiquidAmount {});
+
+    // Delegate most of the locked allocation
+    let res = execute(
+        deps.as_mut(),
+        mock_env_time(0),
+        mock_info("recipient1", &[]),
+        delegate("validator1", 200_000_000_000u128),
+    )
+    .unwrap();
+    sync_delegations(&mut deps, &validators);
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Staking(StakingMsg::Delegate {
+            validator: "validator1".to_string(),
+            amount: coin(200_000_000_000u128, "uluna"),
+        }))]
+    );
+
+    let msg = ExecuteMsg::Redelegate {
+        vesting_id: None,
+        src_validator: "validator1".to_string(),
+        dst_validator: "validator2".to_string(),
+        amount: Uint128::from(50_000_000_000u128),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env_time(0),
+        mock_info("recipient1", &[]),
+        msg.clone(),
+    )
+    .unwrap();
+    sync_delegations(&mut deps, &validators);
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Staking(StakingMsg::Redelegate {
+            src_validator: "validator1".to_string(),
+            dst_validator: "validator2".to_string(),
+            amount: coin(50_000_000_000u128, "uluna"),
+        }))]
+    );
+    let vesting_info = query_vesting(deps.as_ref(), "recipient1".to_string());
+    assert_eq!(
+        vesting_info.delegations,
+        vec![
+            Delegation {
+                validator: "validator1".to_string(),
+                amount: Uint128::from(150_000_000_000u128),
+            },
+            Delegation {
+                validator: "validator2".to_string(),
+                amount: Uint128::from(50_000_000_000u128),
+            },
+        ]
+    );
+
+    let msg = ExecuteMsg::Redelegate {
+        vesting_id: None,
+        src_validator: "validator2".to_string(),
+        dst_validator: "validator1".to_string(),
+        amount: Uint128::from(50_000_000_001u128),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env_time(0),
+        mock_info("recipient1", &[]),
+        msg,
+    )
+    .unwrap_err();
+    assert_eq!(
+        res,
+        ContractError::InsufficientDelegation {
+            validator: "validator2".to_string()
+        }
+    );
+
+    // Only the amount held by the contract can be claimed
+    let env = mock_env_time(SECONDS_PER_PERIOD * 5);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient1", &[]),
+        claim.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient1".to_string(),
+                amount: coins(100_000_000_001u128, "uluna"),
+            }),
+            0u64
+        )]
+    );
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient1", &[]),
+        claim.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::ClaimableStaked {});
+
+    // Undelegated amounts are claimable once unbonded
+    let msg = ExecuteMsg::Undelegate {
+        vesting_id: None,
+        validator: "validator2".to_string(),
+        amount: Uint128::from(50_000_000_000u128),
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient1", &[]),
+        msg,
+    )
+    .unwrap();
+    sync_delegations(&mut deps, &validators);
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Staking(StakingMsg::Undelegate {
+            validator: "validator2".to_string(),
+            amount: coin(50_000_000_000u128, "uluna"),
+        }))]
+    );
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient1", &[]),
+        claim.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::ClaimableStaked {});
+
+    let env = mock_env_time(SECONDS_PER_PERIOD * 5 + UNBONDING_PERIOD);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient1", &[]),
+        claim.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient1".to_string(),
+                amount: coins(50_000_000_000u128, "uluna"),
+            }),
+            0u64
+        )]
+    );
+
+    // Renouncing undelegates everything, leaving the forfeited amount pending until unbonded
+    let msg = ExecuteMsg::Renounce {
+        vesting_id: None,
+        keep_claimable: true,
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient1", &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Staking(StakingMsg::Undelegate {
+            validator: "validator1".to_string(),
+            amount: coin(150_000_000_000u128, "uluna"),
+        }))]
+    );
+    assert!(res
+        .attributes
+        .contains(&attr("forfeit_pending", "50000000001")));
+    let vesting_info = query_vesting(deps.as_ref(), "recipient1".to_string());
+    assert_eq!(vesting_info.delegations, vec![]);
+    assert_eq!(
+        vesting_info.vested_amount,
+        Uint128::from(99_999_999_999u128)
+    );
+    assert_eq!(
+        vesting_info.forfeit_pending,
+        Uint128::from(50_000_000_001u128)
+    );
+
+    let msg = ExecuteMsg::SettleForfeit {
+        recipient: "recipient1".to_string(),
+        vesting_id: None,
+    };
+    let res = execute(deps.as_mut(), env, mock_info("anyone", &[]), msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::NothingToSettle {});
+
+    let env = mock_env_time(SECONDS_PER_PERIOD * 5 + UNBONDING_PERIOD * 2);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("anyone", &[]),
+        msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "master_address".to_string(),
+            amount: coins(50_000_000_001u128, "uluna"),
+        }))]
+    );
+    let res = execute(deps.as_mut(), env.clone(), mock_info("anyone", &[]), msg).unwrap_err();
+    assert_eq!(res, ContractError::NothingToSettle {});
+
+    // The earned amount kept on renouncing is claimable once unbonded
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient1", &[]),
+        claim.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient1".to_string(),
+                amount: coins(99_999_999_999u128, "uluna"),
+            }),
+            0u64
+        )]
+    );
+    let res = execute(deps.as_mut(), env, mock_info("recipient1", &[]), claim).unwrap_err();
+    assert_eq!(res, ContractError::NoClaimable {});
+}
+
+#[test]
+fn test_staking_rewards() {
+    let (mut deps, _) = mock_init();
+    let validators = [Validator {
+        address: "validator1".to_string(),
+        commission: Decimal::percent(5),
+        max_commission: Decimal::percent(10),
+        max_change_rate: Decimal::percent(1),
+    }];
+    let delegation = |amount: u128, rewards: u128| FullDelegation {
+        delegator: Addr::unchecked(MOCK_CONTRACT_ADDR),
+        validator: "validator1".to_string(),
+        amount: coin(amount, "uluna"),
+        can_redelegate: coin(amount, "uluna"),
+        accumulated_rewards: coins(rewards, "uluna"),
+    };
+    deps.querier.update_staking("uluna", &validators, &[]);
+    let query_rewards = |deps: Deps, recipient: &str| -> Vec<Coin> {
+        let msg = QueryMsg::Rewards {
+            recipient: recipient.to_string(),
+        };
+        let res: RewardsResponse = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+        res.rewards
+    };
+
+    for (recipient, amount) in [("recipient1", 100u128), ("recipient2", 300u128)] {
+        let msg = ExecuteMsg::Delegate {
+            vesting_id: None,
+            validator: "validator1".to_string(),
+            amount: Uint128::from(amount),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(recipient, &[]), msg).unwrap();
+        sync_delegations(&mut deps, &validators);
+    }
+
+    // Rewards are credited pro-rata to the delegated amounts, with the rounding remainder left in
+    // the contract
+    deps.querier
+        .update_staking("uluna", &validators, &[delegation(400u128, 1_001u128)]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("recipient1", &[]),
+        ExecuteMsg::WithdrawRewards {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Distribution(
+                DistributionMsg::WithdrawDelegatorReward {
+                    validator: "validator1".to_string(),
+                }
+            )),
+            SubMsg::reply_on_error(
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "recipient1".to_string(),
+                    amount: coins(250u128, "uluna"),
+                }),
+                0u64
+            ),
+        ]
+    );
+    assert_eq!(query_rewards(deps.as_ref(), "recipient1"), vec![]);
+    assert_eq!(
+        query_rewards(deps.as_ref(), "recipient2"),
+        coins(750u128, "uluna")
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("recipient3", &[]),
+        ExecuteMsg::WithdrawRewards {},
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::NoRewards {});
+
+    // Credited rewards are never swept
+    deps.querier.update_staking("uluna", &validators, &[]);
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR,
+        vec![coin(
+            600_000_000_001u128 - 400u128 + 751u128 + 10u128,
+            "uluna",
+        )],
+    );
+    let msg = ExecuteMsg::SweepExcess {
+        denom: "uluna".to_string(),
+        to: "treasury".to_string(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("master_address", &[]),
+        msg,
+    )
+    .unwrap();
+    assert!(res.attributes.contains(&attr("excess_amount", "11")));
+
+    // Rewards are not forfeited, including the ones withdrawn on undelegation
+    deps.querier
+        .update_staking("uluna", &validators, &[delegation(400u128, 4u128)]);
+    let msg = ExecuteMsg::Renounce {
+        vesting_id: None,
+        keep_claimable: false,
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("recipient2", &[]), msg).unwrap();
+    assert_eq!(
+        query_rewards(deps.as_ref(), "recipient2"),
+        coins(753u128, "uluna")
+    );
+
+    deps.querier.update_staking("uluna", &validators, &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("recipient2", &[]),
+        ExecuteMsg::WithdrawRewards {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient2".to_string(),
+                amount: coins(753u128, "uluna"),
+            }),
+            0u64
+        )]
+    );
+}
+
+#[test]
+fn test_slashing() {
+    let (mut deps, _) = mock_init();
+    let validators = [Validator {
+        address: "validator1".to_string(),
+        commission: Decimal::percent(5),
+        max_commission: Decimal::percent(10),
+        max_change_rate: Decimal::percent(1),
+    }];
+    deps.querier.update_staking("uluna", &validators, &[]);
+    for (recipient, amount) in [("recipient1", 100u128), ("recipient2", 300u128)] {
+        let msg = ExecuteMsg::Delegate {
+            vesting_id: None,
+            validator: "validator1".to_string(),
+            amount: Uint128::from(amount),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(recipient, &[]), msg).unwrap();
+        sync_delegations(&mut deps, &validators);
+    }
+    let vested_amount1 = query_vesting(deps.as_ref(), "recipient1".to_string()).vested_amount;
+    let vested_amount2 = query_vesting(deps.as_ref(), "recipient2".to_string()).vested_amount;
+
+    // The validator is slashed by 10%
+    deps.querier.update_staking(
+        "uluna",
+        &validators,
+        &[FullDelegation {
+            delegator: Addr::unchecked(MOCK_CONTRACT_ADDR),
+            validator: "validator1".to_string(),
+            amount: coin(360u128, "uluna"),
+            can_redelegate: coin(360u128, "uluna"),
+            accumulated_rewards: vec![],
+        }],
+    );
+    let undelegate = |amount: u128| ExecuteMsg::Undelegate {
+        vesting_id: None,
+        validator: "validator1".to_string(),
+        amount: Uint128::from(amount),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("recipient1", &[]),
+        undelegate(100u128),
+    )
+    .unwrap_err();
+    assert_eq!(
+        res,
+        ContractError::InsufficientDelegation {
+            validator: "validator1".to_string()
+        }
+    );
+
+    // The loss is booked to each delegating position pro-rata to its delegation
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("recipient1", &[]),
+        undelegate(90u128),
+    )
+    .unwrap();
+    let vesting_info = query_vesting(deps.as_ref(), "recipient1".to_string());
+    assert_eq!(vesting_info.delegations, vec![]);
+    assert_eq!(
+        vesting_info.vested_amount,
+        vested_amount1 - Uint128::from(10u128)
+    );
+    let vesting_info = query_vesting(deps.as_ref(), "recipient2".to_string());
+    assert_eq!(
+        vesting_info.delegations,
+        vec![Delegation {
+            validator: "validator1".to_string(),
+            amount: Uint128::from(270u128),
+        }]
+    );
+    assert_eq!(
+        vesting_info.vested_amount,
+        vested_amount2 - Uint128::from(30u128)
+    );
+    assert_eq!(
+        VALIDATORS
+            .load(&deps.storage, "validator1")
+            .unwrap()
+            .total_amount,
+        Uint128::from(270u128)
+    );
+}
+
+#[test]
+fn test_slashing_then_disapprove() {
+    let mut deps = mock_dependencies(&[]);
+    let validators = [Validator {
+        address: "validator1".to_string(),
+        commission: Decimal::percent(5),
+        max_commission: Decimal::percent(10),
+        max_change_rate: Decimal::percent(1),
+    }];
+    deps.querier.update_staking("uluna", &validators, &[]);
+
+    let msg = InstantiateMsg {
+        master_address: Some("master_address".to_string()),
+        forfeit_address: None,
+        forfeit_mode: None,
+        acceptance_period: None,
+        claim_expiry_duration: None,
+        clock: None,
+        unbonding_period: None,
+        transferable: None,
+        vestings: vec![Vesting {
+            recipient: "recipient1".to_string(),
+            amount: Uint128::from(300u128),
+            start_time: None,
+            cliff_duration: None,
+            mode: None,
+            tollgate_periods: Some(vec![1]),
+            basket: None,
+        }],
+    };
+    let info = mock_info("addr0000", &[coin(300u128, "uluna")]);
+    instantiate(deps.as_mut(), mock_env_time(0), info, msg).unwrap();
+
+    let msg = ExecuteMsg::Delegate {
+        vesting_id: None,
+        validator: "validator1".to_string(),
+        amount: Uint128::from(290u128),
+    };
+    execute(
+        deps.as_mut(),
+        mock_env_time(0),
+        mock_info("recipient1", &[]),
+        msg,
+    )
+    .unwrap();
+
+    // The delegation is slashed below the amount unlocked up to the approved periods
+    deps.querier.update_staking(
+        "uluna",
+        &validators,
+        &[FullDelegation {
+            delegator: Addr::unchecked(MOCK_CONTRACT_ADDR),
+            validator: "validator1".to_string(),
+            amount: coin(10u128, "uluna"),
+            can_redelegate: coin(10u128, "uluna"),
+            accumulated_rewards: vec![],
+        }],
+    );
+
+    // Only what is left after slashing remains claimable, and nothing is forfeited
+    let msg = ExecuteMsg::ApproveTollgate {
+        recipient: "recipient1".to_string(),
+        vesting_id: None,
+        approve: false,
+        payout: None,
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env_time(SECONDS_PER_PERIOD * 2),
+        mock_info("master_address", &[]),
+        msg,
+    )
+    .unwrap();
+    assert!(res
+        .attributes
+        .contains(&Attribute::new("forfeited_amount", "0")));
+    let vesting_info = query_vesting(deps.as_ref(), "recipient1".to_string());
+    assert!(!vesting_info.active);
+    assert_eq!(vesting_info.vested_amount, Uint128::from(20u128));
+}
+
+#[test]
+fn test_vote() {
+    let (mut deps, _) = mock_init();
+    let validators = [Validator {
+        address: "validator1".to_string(),
+        commission: Decimal::percent(5),
+        max_commission: Decimal::percent(10),
+        max_change_rate: Decimal::percent(1),
+    }];
+    deps.querier.update_staking("uluna", &validators, &[]);
+    let vote = |vote: VoteOption| ExecuteMsg::Vote {
+        proposal_id: 1u64,
+        vote,
+    };
+
+    // Only delegations can be voted with, so the master cannot vote with the recipients' funds
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("master_address", &[]),
+        vote(VoteOption::Yes),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::NoDelegation {});
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("recipient1", &[]),
+        vote(VoteOption::Yes),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::NoDelegation {});
+
+    for (recipient, amount) in [("recipient1", 100u128), ("recipient2", 300u128)] {
+        let msg = ExecuteMsg::Delegate {
+            vesting_id: None,
+            validator: "validator1".to_string(),
+            amount: Uint128::from(amount),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(recipient, &[]), msg).unwrap();
+        sync_delegations(&mut deps, &validators);
+    }
+
+    let option = |number: u8, weight: &str| {
+        let len = weight.len() as u8;
+        [&[0x1a, len + 4, 0x08, number, 0x12, len], weight.as_bytes()].concat()
+    };
+    let vote_weighted = |options: &[(u8, &str)]| {
+        let mut value = [&[0x08, 0x01, 0x12, 0x0f][..], MOCK_CONTRACT_ADDR.as_bytes()].concat();
+        for (number, weight) in options {
+            value.extend(option(*number, weight));
+        }
+        vec![SubMsg::new(CosmosMsg::Stargate {
+            type_url: "/cosmos.gov.v1beta1.MsgVoteWeighted".to_string(),
+            value: Binary::from(value),
+        })]
+    };
+
+    // Recipients vote with their own delegations only, and those who have not voted are left out
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("recipient1", &[]),
+        vote(VoteOption::Yes),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vote_weighted(&[(0x01, "1000000000000000000")])
+    );
+
+    // Disagreeing recipients split the vote by weight
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("recipient2", &[]),
+        vote(VoteOption::No),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vote_weighted(&[(0x01, "250000000000000000"), (0x03, "750000000000000000")])
+    );
+
+    let msg = QueryMsg::Votes { proposal_id: 1u64 };
+    let res: VotesResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(
+        res.votes,
+        vec![
+            VoteRecord {
+                voter: Addr::unchecked("recipient1"),
+                vote: VoteOption::Yes,
+                weight: Uint128::from(100u128),
+            },
+            VoteRecord {
+                voter: Addr::unchecked("recipient2"),
+                vote: VoteOption::No,
+                weight: Uint128::from(300u128),
+            },
+        ]
+    );
+
+    // Changing a vote recasts the contract's vote
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("recipient1", &[]),
+        vote(VoteOption::No),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vote_weighted(&[(0x03, "1000000000000000000")])
+    );
+
+    // Votes are weighted by the voters' delegations when they last voted
+    let msg = ExecuteMsg::Delegate {
+        vesting_id: None,
+        validator: "validator1".to_string(),
+        amount: Uint128::from(400u128),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("recipient2", &[]), msg).unwrap();
+    sync_delegations(&mut deps, &validators);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("recipient1", &[]),
+        vote(VoteOption::Yes),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vote_weighted(&[(0x01, "250000000000000000"), (0x03, "750000000000000000")])
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("recipient2", &[]),
+        vote(VoteOption::No),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vote_weighted(&[(0x01, "125000000000000000"), (0x03, "875000000000000000")])
+    );
+}
+
+#[test]
+fn test_transferable_positions() {
+    let (mut deps, _) = mock_init();
+    let transfer = |recipient: &str, token_id: &str| ExecuteMsg::TransferNft {
+        recipient: recipient.to_string(),
+        token_id: token_id.to_string(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("recipient1", &[]),
+        transfer("buyer", "1"),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::NotTransferable {});
+
+    let mut deps = mock_dependencies(&[]);
+    let msg = InstantiateMsg {
+        master_address: Some("master_address".to_string()),
+        forfeit_address: None,
+        forfeit_mode: None,
+        acceptance_period: None,
+        claim_expiry_duration: None,
+        clock: None,
+        unbonding_period: None,
+        transferable: Some(true),
+        vestings: vec![
+            Vesting {
+                recipient: "recipient1".to_string(),
+                amount: Uint128::from(300_000_000_000u128),
+                start_time: None,
+                cliff_duration: None,
+                mode: None,
+                tollgate_periods: Some(vec![1u64]),
+                basket: None,
+            },
+            Vesting {
+                recipient: "recipient2".to_string(),
+                amount: Uint128::from(300_000_000_000u128),
+                start_time: None,
+                cliff_duration: None,
+                mode: None,
+                tollgate_periods: None,
+                basket: None,
+            },
+        ],
+    };
+    let info = mock_info("addr0000", &[coin(600_000_000_000u128, "uluna")]);
+    instantiate(deps.as_mut(), mock_env_time(0), info, msg).unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::ContractInfo {}).unwrap();
+    let contract_info: ContractInfoResponse = from_binary(&res).unwrap();
+    assert_eq!(contract_info.symbol, "VEST".to_string());
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::NumTokens {}).unwrap();
+    let num_tokens: NumTokensResponse = from_binary(&res).unwrap();
+    assert_eq!(num_tokens.count, 2u64);
+
+    // Only the recipient can transfer its position
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("recipient2", &[]),
+        transfer("buyer", "1"),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("recipient1", &[]),
+        transfer("buyer", "3"),
+    )
+    .unwrap_err();
+    assert_eq!(
+        res,
+        ContractError::TokenNotFound {
+            token_id: "3".to_string()
+        }
+    );
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("recipient1", &[]),
+        transfer("buyer", "1"),
+    )
+    .unwrap();
+    let msg = QueryMsg::OwnerOf {
+        token_id: "1".to_string(),
+        include_expired: None,
+    };
+    let owner_of: OwnerOfResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(owner_of.owner, "buyer".to_string());
+    let tokens = |deps: Deps, owner: &str| -> Vec<String> {
+        let msg = QueryMsg::Tokens {
+            owner: owner.to_string(),
+            start_after: None,
+            limit: None,
+        };
+        let res: TokensResponse = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+        res.tokens
+    };
+    assert_eq!(tokens(deps.as_ref(), "buyer"), vec!["1".to_string()]);
+    assert_eq!(tokens(deps.as_ref(), "recipient1"), Vec::<String>::new());
+
+    // Claims go to the new recipient
+    let env = mock_env_time(SECONDS_PER_PERIOD);
+    let claim = ExecuteMsg::Claim {
+        vesting_ids: None,
+        ibc: None,
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient1", &[]),
+        claim.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::NoClaimable {});
+    let res = execute(deps.as_mut(), env.clone(), mock_info("buyer", &[]), claim).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "buyer".to_string(),
+                amount: coins(50_000_000_000u128, "uluna"),
+            }),
+            0u64
+        )]
+    );
+
+    // Tollgate decisions stay with the position
+    let msg = ExecuteMsg::ApproveTollgate {
+        recipient: "buyer".to_string(),
+        vesting_id: Some(1u64),
+        approve: true,
+        payout: None,
+    };
+    execute(deps.as_mut(), env, mock_info("master_address", &[]), msg).unwrap();
+    let msg = QueryMsg::NftInfo {
+        token_id: "1".to_string(),
+    };
+    let nft_info: NftInfoResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(nft_info.extension.recipient, Addr::unchecked("buyer"));
+    assert_eq!(nft_info.extension.approved_periods, 6u64);
+    assert_eq!(
+        nft_info.extension.claimed_amount,
+        Uint128::from(50_000_000_000u128)
+    );
+
+    // Sending a position notifies the receiving contract
+    let msg = ExecuteMsg::SendNft {
+        contract: "market".to_string(),
+        token_id: "1".to_string(),
+        msg: to_binary("list").unwrap(),
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("buyer", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "market".to_string(),
+            msg: to_binary(&ReceiverExecuteMsg::ReceiveNft(Cw721ReceiveMsg {
+                sender: "buyer".to_string(),
+                token_id: "1".to_string(),
+                msg: to_binary("list").unwrap(),
+            }))
+            .unwrap(),
+            funds: vec![],
+        }))]
+    );
+    assert_eq!(tokens(deps.as_ref(), "market"), vec!["1".to_string()]);
+}
+
+#[test]
+fn test_split_and_merge_vestings() {
+    let (mut deps, _) = mock_init();
+    let validators = [Validator {
+        address: "validator1".to_string(),
+        commission: Decimal::percent(5),
+        max_commission: Decimal::percent(10),
+        max_change_rate: Decimal::percent(1),
+    }];
+    deps.querier.update_staking("uluna", &validators, &[]);
+    let env = mock_env_time(SECONDS_PER_PERIOD);
+    let split = |vesting_id: Option<u64>, shares: &[(&str, u128)]| ExecuteMsg::SplitVesting {
+        vesting_id,
+        recipient_shares: shares
+            .iter()
+            .map(|(recipient, weight)| (recipient.to_string(), Uint128::new(*weight)))
+            .collect(),
+    };
+    let query_position = |deps: Deps, recipient: &str, vesting_id: u64| -> VestingInfo {
+        let msg = QueryMsg::VestingInfo {
+            recipient: recipient.to_string(),
+            vesting_id: Some(vesting_id),
+        };
+        from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap()
+    };
+
+    // Claim the first period and stake part of the rest before splitting
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient1", &[]),
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap();
+    let msg = ExecuteMsg::Delegate {
+        vesting_id: None,
+        validator: "validator1".to_string(),
+        amount: Uint128::from(100_000_000_001u128),
+    };
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient1", &[]),
+        msg,
+    )
+    .unwrap();
+    sync_delegations(&mut deps, &validators);
+
+    // Positions which are not transferable cannot be split to other recipients
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient1", &[]),
+        split(None, &[("recipient1", 1u128), ("buyer", 1u128)]),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::NotTransferable {});
+    CONFIG
+        .update(&mut deps.storage, |mut config| -> StdResult<_> {
+            config.transferable = true;
+            Ok(config)
+        })
+        .unwrap();
+
+    // Shares must have non-zero weights and amounts
+    for shares in [
+        vec![],
+        vec![("team_a", 0u128), ("team_b", 1u128)],
+        vec![("team_a", 1u128), ("team_b", 1_000_000_000_000u128)],
+    ] {
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("recipient1", &[]),
+            split(None, &shares),
+        )
+        .unwrap_err();
+        assert_eq!(res, ContractError::InvalidShares {});
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient1", &[]),
+        split(None, &[("team_a", 1u128), ("team_b", 3u128)]),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Distribution(
+            DistributionMsg::WithdrawDelegatorReward {
+                validator: "validator1".to_string(),
+            }
+        ))]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("method", "try_split_vesting"),
+            attr("recipient", "recipient1"),
+            attr("vesting_id", "1"),
+            attr("new_vesting_ids", "3,4"),
+        ]
+    );
+
+    // The amounts are conserved, with the rounding remainders going to the last share
+    let team_a = query_position(deps.as_ref(), "team_a", 3u64);
+    let team_b = query_position(deps.as_ref(), "team_b", 4u64);
+    assert_eq!(team_a.total_amount, Uint128::from(75_000_000_000u128));
+    assert_eq!(team_a.claimed_amount, Uint128::from(12_500_000_000u128));
+    assert_eq!(team_a.vested_amount, Uint128::from(62_500_000_000u128));
+    assert_eq!(
+        team_a.delegations,
+        vec![Delegation {
+            validator: "validator1".to_string(),
+            amount: Uint128::from(25_000_000_000u128),
+        }]
+    );
+    assert_eq!(team_b.total_amount, Uint128::from(225_000_000_001u128));
+    assert_eq!(team_b.claimed_amount, Uint128::from(37_500_000_000u128));
+    assert_eq!(team_b.vested_amount, Uint128::from(187_500_000_001u128));
+    assert_eq!(
+        team_b.delegations,
+        vec![Delegation {
+            validator: "validator1".to_string(),
+            amount: Uint128::from(75_000_000_001u128),
+        }]
+    );
+    assert_eq!(team_b.approved_periods, 6u64);
+    assert_eq!(team_b.total_periods, 6u64);
+    assert_eq!(team_b.amount_per_period, Uint128::from(37_500_000_000u128));
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::NumTokens {}).unwrap();
+    let num_tokens: NumTokensResponse = from_binary(&res).unwrap();
+    assert_eq!(num_tokens.count, 3u64);
+
+    // The claimed period stays claimed
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("team_a", &[]),
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::NoClaimable {});
+
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient2", &[]),
+        split(None, &[("team_a", 1u128), ("team_c", 1u128)]),
+    )
+    .unwrap();
+
+    let merge = |vesting_ids: &[u64]| ExecuteMsg::MergeVestings {
+        vesting_ids: vesting_ids.to_vec(),
+    };
+    for vesting_ids in [vec![3u64], vec![3u64, 3u64]] {
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("team_a", &[]),
+            merge(&vesting_ids),
+        )
+        .unwrap_err();
+        assert_eq!(res, ContractError::InvalidMerge {});
+    }
+
+    // Only positions with identical schedules can be merged
+    let msg = ExecuteMsg::AddVestings {
+        vestings: vec![Vesting {
+            recipient: "team_a".to_string(),
+            amount: Uint128::from(1_000u128),
+            start_time: Some(0u64),
+            cliff_duration: None,
+            mode: Some(VestingMode::Linear),
+            tollgate_periods: None,
+            basket: None,
+        }],
+    };
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("master_address", &coins(1_000u128, "uluna")),
+        msg,
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("team_a", &[]),
+        merge(&[3u64, 7u64]),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::MismatchedSchedules {});
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("team_a", &[]),
+        merge(&[3u64, 5u64]),
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("method", "try_merge_vestings"),
+            attr("recipient", "team_a"),
+            attr("vesting_id", "3"),
+            attr("merged_vesting_ids", "5"),
+            attr("total_amount", "225000000000"),
+        ]
+    );
+    let merged = query_position(deps.as_ref(), "team_a", 3u64);
+    assert_eq!(merged.claimed_amount, Uint128::from(12_500_000_000u128));
+    assert_eq!(merged.vested_amount, Uint128::from(212_500_000_000u128));
+    assert_eq!(merged.amount_per_period, Uint128::from(37_500_000_000u128));
+
+    // The merged position claims the unclaimed first period of the second one
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("team_a", &[]),
+        ExecuteMsg::Claim {
+            vesting_ids: Some(vec![3u64]),
+            ibc: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "team_a".to_string(),
+                amount: coins(25_000_000_000u128, "uluna"),
+            }),
+            0u64
+        )]
+    );
+}
+
+#[test]
+fn test_ibc_claim() {
+    let (mut deps, _) = mock_init();
+    let env = mock_env_time(SECONDS_PER_PERIOD);
+    let claim = |channel_id: &str| ExecuteMsg::Claim {
+        vesting_ids: None,
+        ibc: Some(IbcClaim {
+            channel_id: channel_id.to_string(),
+            remote_address: "osmo1recipient".to_string(),
+            timeout: None,
+        }),
+    };
+    let query_payouts = |deps: Deps| -> Vec<IbcPayout> {
+        let msg = QueryMsg::IbcPayouts {
+            start_after: None,
+            limit: None,
+        };
+        let res: IbcPayoutsResponse = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+        res.payouts
+    };
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient1", &[]),
+        claim(""),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::InvalidIbcClaim {});
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient1", &[]),
+        claim("channel-0"),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Ibc(IbcMsg::Transfer {
+                channel_id: "channel-0".to_string(),
+                to_address: "osmo1recipient".to_string(),
+                amount: coin(50_000_000_000u128, "uluna"),
+                timeout: IbcTimeout::with_timestamp(Timestamp::from_seconds(
+                    SECONDS_PER_PERIOD + IBC_TIMEOUT_SECONDS
+                )),
+            }),
+            1u64,
+        )]
+    );
+    assert_eq!(
+        query_payouts(deps.as_ref()),
+        vec![IbcPayout {
+            payout_id: 1u64,
+            recipient: Addr::unchecked("recipient1"),
+            channel_id: "channel-0".to_string(),
+            remote_address: "osmo1recipient".to_string(),
+            amount: coin(50_000_000_000u128, "uluna"),
+            claims: vec![ClaimedAmount {
+                vesting_id: 1u64,
+                amount: Uint128::from(50_000_000_000u128),
+            }],
+            timeout: SECONDS_PER_PERIOD + IBC_TIMEOUT_SECONDS,
+        }]
+    );
+
+    // A failed transfer restores the claim
+    let msg = Reply {
+        id: 1u64,
+        result: SubMsgResult::Err("channel not found".to_string()),
+    };
+    reply(deps.as_mut(), env.clone(), msg).unwrap();
+    let vesting_info = query_vesting(deps.as_ref(), "recipient1".to_string());
+    assert_eq!(vesting_info.claimed_amount, Uint128::zero());
+    assert_eq!(
+        vesting_info.vested_amount,
+        Uint128::from(300_000_000_001u128)
+    );
+    assert_eq!(query_payouts(deps.as_ref()), vec![]);
+
+    // A refunded transfer is restored once the refund is held by the contract
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient1", &[]),
+        claim("channel-0"),
+    )
+    .unwrap();
+    let resolve = |payout_id: u64, delivered: bool| ExecuteMsg::ResolveIbcPayout {
+        payout_id,
+        delivered,
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient1", &[]),
+        resolve(2u64, false),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    // The position cannot be carved up while the payout may still be restored to it
+    let msg = ExecuteMsg::SplitVesting {
+        vesting_id: None,
+        recipient_shares: vec![
+            ("recipient1".to_string(), Uint128::new(1u128)),
+            ("recipient1".to_string(), Uint128::new(1u128)),
+        ],
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient1", &[]),
+        msg,
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::PendingIbcPayout { vesting_id: 1u64 });
+
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, coins(550_000_000_001u128, "uluna"));
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("master_address", &[]),
+        resolve(2u64, false),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::IbcPayoutNotRefunded { payout_id: 2u64 });
+
+    // The unresolved payout cannot be swept either
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, coins(600_000_000_001u128, "uluna"));
+    let msg = ExecuteMsg::SweepExcess {
+        denom: "uluna".to_string(),
+        to: "master_address".to_string(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("master_address", &[]),
+        msg,
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::NoExcess {});
+
+    // Only an admin may restore a refunded payout, even once its transfer has timed out
+    let res = execute(
+        deps.as_mut(),
+        mock_env_time(SECONDS_PER_PERIOD + IBC_TIMEOUT_SECONDS + IBC_PAYOUT_EXPIRY_SECONDS),
+        mock_info("recipient1", &[]),
+        resolve(2u64, false),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("master_address", &[]),
+        resolve(2u64, false),
+    )
+    .unwrap();
+    let vesting_info = query_vesting(deps.as_ref(), "recipient1".to_string());
+    assert_eq!(vesting_info.claimed_amount, Uint128::zero());
+
+    // A delivered transfer stays claimed
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient1", &[]),
+        claim("channel-0"),
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        env,
+        mock_info("master_address", &[]),
+        resolve(3u64, true),
+    )
+    .unwrap();
+    let vesting_info = query_vesting(deps.as_ref(), "recipient1".to_string());
+    assert_eq!(
+        vesting_info.claimed_amount,
+        Uint128::from(50_000_000_000u128)
+    );
+    assert_eq!(query_payouts(deps.as_ref()), vec![]);
+
+    // Anyone may prune an expired payout as delivered, which unblocks its position
+    execute(
+        deps.as_mut(),
+        mock_env_time(SECONDS_PER_PERIOD * 2),
+        mock_info("recipient1", &[]),
+        claim("channel-0"),
+    )
+    .unwrap();
+    let timeout = SECONDS_PER_PERIOD * 2 + IBC_TIMEOUT_SECONDS;
+    let res = execute(
+        deps.as_mut(),
+        mock_env_time(timeout + IBC_PAYOUT_EXPIRY_SECONDS - 1),
+        mock_info("anyone", &[]),
+        resolve(4u64, true),
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+    execute(
+        deps.as_mut(),
+        mock_env_time(timeout + IBC_PAYOUT_EXPIRY_SECONDS),
+        mock_info("anyone", &[]),
+        resolve(4u64, true),
+    )
+    .unwrap();
+    assert_eq!(query_payouts(deps.as_ref()), vec![]);
+    let msg = ExecuteMsg::SplitVesting {
+        vesting_id: None,
+        recipient_shares: vec![
+            ("recipient1".to_string(), Uint128::new(1u128)),
+            ("recipient1".to_string(), Uint128::new(1u128)),
+        ],
+    };
+    execute(
+        deps.as_mut(),
+        mock_env_time(timeout + IBC_PAYOUT_EXPIRY_SECONDS),
+        mock_info("recipient1", &[]),
+        msg,
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_failed_payout() {
+    let (mut deps, _) = mock_init();
+    let env = mock_env_time(SECONDS_PER_PERIOD);
+    let query_credits = |deps: Deps| -> Vec<Coin> {
+        let msg = QueryMsg::Credits {
+            recipient: "recipient1".to_string(),
+        };
+        let res: CreditsResponse = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+        res.credits
+    };
+    let payout_failure = Reply {
+        id: 0u64,
+        result: SubMsgResult::Err("recipient1 is not allowed to receive funds".to_string()),
+    };
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient1", &[]),
+        ExecuteMsg::Claim {
+            vesting_ids: None,
+            ibc: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient1".to_string(),
+                amount: coins(50_000_000_000u128, "uluna"),
+            }),
+            0u64,
+        )]
+    );
+
+    // A failed payout is credited to the recipient, and the vesting stays claimed
+    let res = reply(deps.as_mut(), env.clone(), payout_failure.clone()).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("method", "reply"),
+            attr("recipient", "recipient1"),
+            attr("credited_amount", "50000000000uluna"),
+            attr("error", "recipient1 is not allowed to receive funds"),
+        ]
+    );
+    assert_eq!(
+        query_credits(deps.as_ref()),
+        coins(50_000_000_000u128, "uluna")
+    );
+    let vesting_info = query_vesting(deps.as_ref(), "recipient1".to_string());
+    assert_eq!(
+        vesting_info.claimed_amount,
+        Uint128::from(50_000_000_000u128)
+    );
+
+    // Credits are not swept
+    deps.querier
+        .update_balance(MOCK_CONTRACT_ADDR, coins(600_000_000_001u128, "uluna"));
+    let msg = ExecuteMsg::SweepExcess {
+        denom: "uluna".to_string(),
+        to: "master_address".to_string(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("master_address", &[]),
+        msg,
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::NoExcess {});
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient2", &[]),
+        ExecuteMsg::RetryPayout {},
+    )
+    .unwrap_err();
+    assert_eq!(res, ContractError::NoCredits {});
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient1", &[]),
+        ExecuteMsg::RetryPayout {},
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient1".to_string(),
+                amount: coins(50_000_000_000u128, "uluna"),
+            }),
+            0u64,
+        )]
+    );
+    assert_eq!(query_credits(deps.as_ref()), vec![]);
+
+    // A retry which fails again is credited again
+    reply(deps.as_mut(), env, payout_failure).unwrap();
+    assert_eq!(
+        query_credits(deps.as_ref()),
+        coins(50_000_000_000u128, "uluna")
+    );
+}
+
+#[test]
+fn test_claim_hook() {
+    let (mut deps, _) = mock_init();
+    let env = mock_env_time(SECONDS_PER_PERIOD);
+    let set_hook = |hook: Option<&str>| ExecuteMsg::SetClaimHook {
+        hook: hook.map(|hook| hook.to_string()),
+    };
+    let query_hook = |deps: Deps| -> Option<Addr> {
+        let msg = QueryMsg::ClaimHook {
+            recipient: "recipient1".to_string(),
+        };
+        let res: ClaimHookResponse = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+        res.hook
+    };
+    deps.querier.update_wasm(|query| match query {
+        WasmQuery::ContractInfo { contract_addr } if contract_addr == "hook_contract" => {
+            let contract_info = cosmwasm_std::ContractInfoResponse::new(1u64, "creator");
+            SystemResult::Ok(ContractResult::Ok(to_binary(&contract_info).unwrap()))
+        }
+        _ => SystemResult::Err(SystemError::NoSuchContract {
+            addr: "unknown".to_string(),
+        }),
+    });
+
+    // Only contracts can be registered
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient1", &[]),
+        set_hook(Some("recipient2")),
+    )
+    .unwrap_err();
+    assert_eq!(
+        res,
+        ContractError::InvalidClaimHook {
+            hook: "recipient2".to_string()
+        }
+    );
+    assert_eq!(query_hook(deps.as_ref()), None);
+
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient1", &[]),
+        set_hook(Some("hook_contract")),
+    )
+    .unwrap();
+    assert_eq!(
+        query_hook(deps.as_ref()),
+        Some(Addr::unchecked("hook_contract"))
+    );
+
+    // Claims are sent to the hook along with a notification
+    let claim = ExecuteMsg::Claim {
+        vesting_ids: None,
+        ibc: None,
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("recipient1", &[]),
+        claim.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "hook_contract".to_string(),
+                msg: to_binary(&ClaimHookExecuteMsg::VestingClaimHook(VestingClaimHook {
+                    amount: coins(50_000_000_000u128, "uluna"),
+                    positions: vec![ClaimedPosition {
+                        vesting_id: 1u64,
+                        periods: 1u64,
+                    }],
+                }))
+                .unwrap(),
+                funds: coins(50_000_000_000u128, "uluna"),
+            }),
+            0u64,
+        )]
+    );
+
+    // A failing hook credits the claim to the recipient
+    let msg = Reply {
+        id: 0u64,
+        result: SubMsgResult::Err("hook failed".to_string()),
+    };
+    reply(deps.as_mut(), env.clone(), msg).unwrap();
+    let msg = QueryMsg::Credits {
+        recipient: "recipient1".to_string(),
+    };
+    let credits: CreditsResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(credits.credits, coins(50_000_000_000u128, "uluna"));
+
+    // Claims are sent to the recipient once the hook is removed
+    execute(
+        deps.as_mut(),
+        env,
+        mock_info("recipient1", &[]),
+        set_hook(None),
+    )
+    .unwrap();
+    assert_eq!(query_hook(deps.as_ref()), None);
+    let res = execute(
+        deps.as_mut(),
+        mock_env_time(SECONDS_PER_PERIOD * 2),
+        mock_info("recipient1", &[]),
+        claim,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient1".to_string(),
+                amount: coins(50_000_000_000u128, "uluna"),
+            }),
+            0u64,
+        )]
+    );
+}
+
+#[test]
+fn test_migrate() {
+    let mut deps = mock_dependencies(&[]);
+    LEGACY_CONFIG
+        .save(
+            deps.as_mut().storage,
+            &LegacyConfig {
+                master_address: Addr::unchecked("master_address"),
+                denom: "uluna".to_string(),
+                vesting_start_time: 1_000u64,
+            },
+        )
+        .unwrap();
+    for (recipient, claimed_periods) in [("recipient1", 1u64), ("recipient2", 0u64)] {
+        LEGACY_VESTING_INFO
+            .save(
+                deps.as_mut().storage,
+                &Addr::unchecked(recipient),
+                &LegacyVestingInfo {
+                    recipient: Addr::unchecked(recipient),
+                    active: true,
+                    approved_periods: 6u64,
+                    total_periods: 6u64,
+                    last_claimed_period: claimed_periods,
+                    total_amount: Uint128::from(600u128),
+                    claimed_amount: Uint128::from(100u128 * claimed_periods as u128),
+                    vested_amount: Uint128::from(600u128 - 100u128 * claimed_periods as u128),
+                    amount_per_period: Uint128::from(100u128),
+                },
+            )
+            .unwrap();
+    }
+
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![attr("method", "migrate"), attr("migrated_vestings", "2")]
+    );
+
+    // The master keeps all its powers as the holder of every role
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Roles {}).unwrap();
+    let roles: RolesResponse = from_binary(&res).unwrap();
+    let master = vec!["master_address".to_string()];
+    assert_eq!(
+        roles,
+        RolesResponse {
+            admins: master.clone(),
+            reviewers: master.clone(),
+            guardians: master.clone(),
+            funders: master,
+        }
+    );
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(config.forfeit_address, "master_address".to_string());
+    assert_eq!(config.vesting_start_time, 1_000u64);
+    assert_eq!(config.clock, Clock::Time);
+
+    // Each vesting becomes a position on the original schedule
+    let vesting_info = query_vesting(deps.as_ref(), "recipient1".to_string());
+    assert_eq!(vesting_info.vesting_id, 1u64);
+    assert_eq!(vesting_info.start_time, 1_000u64);
+    assert_eq!(vesting_info.last_claimed_period, 1u64);
+    assert_eq!(vesting_info.vested_amount, Uint128::from(500u128));
+    assert_eq!(vesting_info.mode, VestingMode::Step);
+    let vesting_info = query_vesting(deps.as_ref(), "recipient2".to_string());
+    assert_eq!(vesting_info.vesting_id, 2u64);
+
+    let msg = ExecuteMsg::Claim {
+        vesting_ids: None,
+        ibc: None,
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env_time(1_000u64 + SECONDS_PER_PERIOD * 2),
+        mock_info("recipient1", &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "recipient1".to_string(),
+                amount: coins(100u128, "uluna"),
+            }),
+            0u64,
+        )]
+    );
+
+    // Migrating an instance which is already converted leaves its storage as is
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!(res.attributes, vec![attr("method", "migrate")]);
+    let vesting_info = query_vesting(deps.as_ref(), "recipient2".to_string());
+    assert_eq!(vesting_info.vesting_id, 2u64);
+}